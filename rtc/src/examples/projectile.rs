@@ -1,4 +1,4 @@
-use crate::{
+use rtc::{
     primitives::{
         canvas::Canvas,
         pixel::Pixel,
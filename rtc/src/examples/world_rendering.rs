@@ -1,4 +1,4 @@
-use crate::{
+use rtc::{
     primitives::{
         canvas::Canvas,
         pixel::Pixel,
@@ -7,9 +7,9 @@ use crate::{
         transformation_matrices,
         vector::{point, vector, Point, Transformation},
     },
-    scenes::{Camera, World},
+    scenes::{Camera, Integrator, PathTracer, World},
     shading::*,
-    shapes::{Shape, SPHERE},
+    shapes::{Motion, Shape, Sphere},
     utils::typelevel_nums::*,
 };
 
@@ -47,12 +47,12 @@ pub fn world_rendering_1() -> std::io::Result<()> {
         vec![
             Shape::new_sphere(green, Transformation::new_translation(6., 5., 1.)),
             Shape::new(
-                SPHERE,
+                Sphere,
                 grey,
                 Transformation::new_scaling(3., 3., 3.).translated(3., 3., 3.),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 purpleish,
                 Transformation::new_scaling(4., 1., 1.)
                     .rotated_z(consts::FRAC_PI_2)
@@ -60,38 +60,41 @@ pub fn world_rendering_1() -> std::io::Result<()> {
             ),
             // floors and walls
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(1000., 1000., 0.01),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(0.01, 1000., 1000.),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(1000., 0.01, 1000.),
             ),
             // arrow thingies
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_blue,
                 Transformation::new_scaling(1000., 0.1, 0.1),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_green,
                 Transformation::new_scaling(0.1, 1000., 0.1),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_red,
                 Transformation::new_scaling(0.1, 0.1, 1000.),
             ),
         ],
-        vec![PointLight::new(point(10., 10., 1.), Color::white() * 2.)],
+        vec![Box::new(PointLight::new(
+            point(10., 10., 1.),
+            Color::white() * 2.,
+        ))],
     );
 
     let from = point(12., 5., 15.);
@@ -129,48 +132,48 @@ pub fn world_rendering_2() -> std::io::Result<()> {
         vec![
             Shape::new_sphere(grey.clone(), Transformation::new_translation(6., 3., 6.)),
             Shape::new(
-                SPHERE,
+                Sphere,
                 grey.clone(),
                 Transformation::new_scaling(1., 3., 1.).translated(6., 8., 6.),
             ),
             // floors and walls
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(1000., 1000., 0.01),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(0.01, 1000., 1000.),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 base_mat.clone(),
                 Transformation::new_scaling(1000., 0.01, 1000.),
             ),
             // arrow thingies
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_blue,
                 Transformation::new_scaling(1000., 0.1, 0.1),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_green,
                 Transformation::new_scaling(0.1, 1000., 0.1),
             ),
             Shape::new(
-                SPHERE,
+                Sphere,
                 arrow_red,
                 Transformation::new_scaling(0.1, 0.1, 1000.),
             ),
         ],
         vec![
-            PointLight::new(point(6., 13., 6.), Color::white() * 0.8),
-            PointLight::new(l1, Color::from((100, 20, 20)) * 3.),
-            PointLight::new(l2, Color::from((20, 20, 100)) * 3.),
-            PointLight::new(l3, Color::from((20, 100, 20)) * 3.),
+            Box::new(PointLight::new(point(6., 13., 6.), Color::white() * 0.8)),
+            Box::new(PointLight::new(l1, Color::from((100, 20, 20)) * 3.)),
+            Box::new(PointLight::new(l2, Color::from((20, 20, 100)) * 3.)),
+            Box::new(PointLight::new(l3, Color::from((20, 100, 20)) * 3.)),
         ],
     );
 
@@ -208,7 +211,10 @@ pub fn world_rendering_3() -> std::io::Result<()> {
                 ),
                 Shape::new_plane(space_blue.clone(), Transformation::identity()),
             ],
-            vec![PointLight::new(point(-100., 10., 0.), Color::white() * 3.8)],
+            vec![Box::new(PointLight::new(
+                point(-100., 10., 0.),
+                Color::white() * 3.8,
+            ))],
         );
 
         let from = point(12., 6., 12.);
@@ -287,7 +293,10 @@ pub fn world_rendering_4() -> std::io::Result<()> {
                 Transformation::new_z_rotation(consts::FRAC_PI_2).translated(-5., 0., 0.),
             ),
         ],
-        vec![PointLight::new(point(10., 10., 0.), Color::white() * 3.8)],
+        vec![Box::new(PointLight::new(
+            point(10., 10., 0.),
+            Color::white() * 3.8,
+        ))],
     );
 
     let from = point(12., 6., 12.);
@@ -330,48 +339,48 @@ pub fn world_rendering_5() -> std::io::Result<()> {
             vec![
                 Shape::new_sphere(grey.clone(), Transformation::new_translation(6., 3., 6.)),
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     grey.clone(),
                     Transformation::new_scaling(1., 3., 1.).translated(6., 8., 6.),
                 ),
                 // floors and walls
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     base_mat.clone(),
                     Transformation::new_scaling(1000., 1000., 0.01),
                 ),
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     base_mat.clone(),
                     Transformation::new_scaling(0.01, 1000., 1000.),
                 ),
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     base_mat.clone(),
                     Transformation::new_scaling(1000., 0.01, 1000.),
                 ),
                 // arrow thingies
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     arrow_blue.clone(),
                     Transformation::new_scaling(1000., 0.1, 0.1),
                 ),
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     arrow_green.clone(),
                     Transformation::new_scaling(0.1, 1000., 0.1),
                 ),
                 Shape::new(
-                    SPHERE,
+                    Sphere,
                     arrow_red.clone(),
                     Transformation::new_scaling(0.1, 0.1, 1000.),
                 ),
             ],
             vec![
-                PointLight::new(point(6., 13., 6.), Color::white() * 0.8),
-                PointLight::new(l1, Color::from((100, 20, 20)) * 3.),
-                PointLight::new(l2, Color::from((20, 20, 100)) * 3.),
-                PointLight::new(l3, Color::from((20, 100, 20)) * 3.),
+                Box::new(PointLight::new(point(6., 13., 6.), Color::white() * 0.8)),
+                Box::new(PointLight::new(l1, Color::from((100, 20, 20)) * 3.)),
+                Box::new(PointLight::new(l2, Color::from((20, 20, 100)) * 3.)),
+                Box::new(PointLight::new(l3, Color::from((20, 100, 20)) * 3.)),
             ],
         );
 
@@ -475,7 +484,10 @@ pub fn world_rendering_6() -> std::io::Result<()> {
                 Transformation::new_translation(0., 10., 0.),
             ),
         ],
-        vec![PointLight::new(point(10., 10., 0.), Color::white() * 3.8)],
+        vec![Box::new(PointLight::new(
+            point(10., 10., 0.),
+            Color::white() * 3.8,
+        ))],
     );
 
     let from = point(20., 10., 5.);
@@ -541,7 +553,10 @@ pub fn world_rendering_7() -> std::io::Result<()> {
                 Transformation::new_translation(0., -5., 0.),
             ),
         ],
-        vec![PointLight::new(point(0., 20., 0.), Color::white() * 3.8)],
+        vec![Box::new(PointLight::new(
+            point(0., 20., 0.),
+            Color::white() * 3.8,
+        ))],
     );
 
     let from = point(5., 10., 5.);
@@ -561,6 +576,11 @@ pub fn world_rendering_7() -> std::io::Result<()> {
 }
 
 pub fn world_rendering_8() -> std::io::Result<()> {
+    spinning_cube("world_render_8", false)
+}
+
+/// Frames of a cube spinning below a glass sphere, saved as `<name>_<frame>`
+fn spinning_cube(name: &str, motion_blur: bool) -> std::io::Result<()> {
     let checkers = Material::new_with_pattern(
         Color::from((50, 50, 70)),
         Some(Pattern::new(
@@ -601,16 +621,32 @@ pub fn world_rendering_8() -> std::io::Result<()> {
     );
     let mirror = Material::new(Color::from((1, 1, 1)), 0.2, 0.8, 0.8, 200., 0.9, 0., 1.);
     let frames = 36;
+    let cube_transform = |angle: f64| {
+        Transformation::new_scaling(3., 0.5, 3.)
+            .rotated_z(angle * 0.5)
+            .rotated_y(angle * 0.75)
+    };
+    // keyframes in between the frames keep the rotating cube from shrinking while blurred
+    let keyframes = 4;
     for i in 0..frames {
         let angle = consts::PI * 2. * i as f64 / frames as f64;
+        let step = consts::PI * 2. / frames as f64;
+        let mut cube = Shape::new_cube(mirror.clone(), cube_transform(angle));
+        if motion_blur {
+            Motion::new(
+                (0..=keyframes)
+                    .map(|k| {
+                        let s = k as f64 / keyframes as f64;
+                        (s, cube_transform(angle + s * step))
+                    })
+                    .collect(),
+            )
+            .and_then(|motion| cube.set_motion(motion))
+            .expect("The rotating cube can always be inverted.");
+        }
         let world = World::new(
             vec![
-                Shape::new_cube(
-                    mirror.clone(),
-                    Transformation::new_scaling(3., 0.5, 3.)
-                        .rotated_z(angle * 0.5)
-                        .rotated_y(angle * 0.75),
-                ),
+                cube,
                 Shape::new_sphere(glass.clone(), Transformation::new_translation(0., 6., 0.)),
                 Shape::new_plane(
                     checkers.clone(),
@@ -621,23 +657,101 @@ pub fn world_rendering_8() -> std::io::Result<()> {
                     Transformation::new_translation(0., 25., 0.),
                 ),
             ],
-            vec![PointLight::new(point(0., 0., 5.), Color::white() * 3.8)],
+            // the objects are about 5 units away from the light, a 1200 W light gives them an
+            // intensity of about 3.8
+            vec![Box::new(PointLight::with_power(
+                point(0., 0., 5.),
+                Color::white(),
+                1200.,
+            ))],
         );
 
         let from = point(0., 10., 0.);
         let to = point(0., 0., 0.);
         let up = vector(1., 0., 0.);
 
-        let camera = Camera::new(
+        let mut camera = Camera::new(
             900,
             900,
             consts::FRAC_PI_2,
             Transformation::new_view(&from, &to, &up),
         );
+        if motion_blur {
+            camera.set_shutter(0., 1.);
+            camera.set_samples(8);
+        }
 
         let canvas = camera.render(world);
-        let r = Rendering::new(format!("world_render_8_{}", i), canvas);
+        let r = Rendering::new(format!("{}_{}", name, i), canvas);
         r.save_to_file()?;
     }
     Ok(())
 }
+
+/// A Cornell box rendered with the path tracer, the colored walls bleed onto the floor and the
+/// blocks
+pub fn world_rendering_9() -> std::io::Result<()> {
+    let wall = |color: Color| Material::new(color, 0., 0.9, 0., 200., 0., 0., 1.);
+    let white = wall(Color::new_rgb(0.73, 0.73, 0.73));
+    let red = wall(Color::new_rgb(0.65, 0.05, 0.05));
+    let green = wall(Color::new_rgb(0.12, 0.45, 0.15));
+    let world = World::new(
+        vec![
+            Shape::new_plane(white.clone(), Transformation::identity()),
+            Shape::new_plane(white.clone(), Transformation::new_translation(0., 10., 0.)),
+            Shape::new_plane(
+                white.clone(),
+                Transformation::new_x_rotation(consts::FRAC_PI_2).translated(0., 0., 10.),
+            ),
+            Shape::new_plane(
+                red,
+                Transformation::new_z_rotation(consts::FRAC_PI_2).translated(-5., 0., 0.),
+            ),
+            Shape::new_plane(
+                green,
+                Transformation::new_z_rotation(consts::FRAC_PI_2).translated(5., 0., 0.),
+            ),
+            Shape::new_cube(
+                white.clone(),
+                Transformation::new_scaling(1.5, 3., 1.5)
+                    .rotated_y(0.3)
+                    .translated(-1.8, 3., 6.),
+            ),
+            Shape::new_cube(
+                white,
+                Transformation::new_scaling(1.5, 1.5, 1.5)
+                    .rotated_y(-0.3)
+                    .translated(1.8, 1.5, 3.5),
+            ),
+        ],
+        vec![Box::new(RectangleLight::new(
+            point(-1., 9.99, 4.),
+            vector(2., 0., 0.),
+            4,
+            vector(0., 0., 2.),
+            4,
+            Color::white() * 1.5,
+        ))],
+    );
+
+    let from = point(0., 5., -9.);
+    let to = point(0., 5., 0.);
+    let up = vector(0., 1., 0.);
+    let mut camera = Camera::new(
+        500,
+        500,
+        consts::FRAC_PI_3,
+        Transformation::new_view(&from, &to, &up),
+    );
+    camera.set_integrator(Integrator::PathTracer(PathTracer::default()));
+    camera.set_samples(256);
+
+    let canvas = camera.render(world);
+    let r = Rendering::new("world_render_9", canvas);
+    r.save_to_file()
+}
+
+/// The spinning cube of `world_rendering_8` blurred by its motion while the shutter is open
+pub fn world_rendering_10() -> std::io::Result<()> {
+    spinning_cube("world_render_10", true)
+}
@@ -0,0 +1,12 @@
+#![recursion_limit = "4096"]
+#![allow(clippy::cast_lossless)]
+#![allow(dead_code)]
+extern crate num_traits;
+
+pub mod parsers;
+pub mod primitives;
+mod profile;
+pub mod scenes;
+pub mod shading;
+pub mod shapes;
+pub mod utils;
@@ -0,0 +1,127 @@
+//! Parser for Bézier patch files (`.bpt`) as used for the Utah teapot
+
+use std::{convert::TryInto, fmt, fs, io, path::Path};
+
+use crate::{
+    primitives::vector::{point, Point, Transformation},
+    shading::Material,
+    shapes::{BezierPatch, PatchIntersection, Shape},
+};
+
+#[derive(Debug)]
+pub enum BptError {
+    Io(io::Error),
+    /// The file ended before all announced patches were read
+    UnexpectedEof,
+    /// A number couldn't be parsed, together with the line it appeared on
+    MalformedNumber {
+        line: usize,
+        found: String,
+    },
+    /// Only bicubic patches are supported
+    UnsupportedDegree {
+        line: usize,
+        u: usize,
+        v: usize,
+    },
+}
+
+impl fmt::Display for BptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BptError::Io(e) => write!(f, "{}", e),
+            BptError::UnexpectedEof => write!(f, "unexpected end of patch data"),
+            BptError::MalformedNumber { line, found } => {
+                write!(f, "line {}: malformed number `{}`", line, found)
+            }
+            BptError::UnsupportedDegree { line, u, v } => write!(
+                f,
+                "line {}: only bicubic patches are supported but found degree {}x{}",
+                line, u, v
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for BptError {
+    fn from(e: io::Error) -> Self {
+        BptError::Io(e)
+    }
+}
+
+/// The contents of a `.bpt` file: the number of patches followed by the patches themselves, each
+/// starting with its degree in u and v followed by one control point per line.
+#[derive(Clone, Debug)]
+pub struct BptFile {
+    pub patches: Vec<BezierPatch>,
+}
+
+impl BptFile {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, BptError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, BptError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.split_whitespace().collect::<Vec<_>>()))
+            .filter(|(_, tokens)| !tokens.is_empty());
+        let mut next_numbers = |count: usize| -> Result<(usize, Vec<f64>), BptError> {
+            let (line, tokens) = lines.next().ok_or(BptError::UnexpectedEof)?;
+            let numbers = tokens
+                .iter()
+                .take(count)
+                .map(|s| {
+                    s.parse().map_err(|_| BptError::MalformedNumber {
+                        line,
+                        found: s.to_string(),
+                    })
+                })
+                .collect::<Result<Vec<f64>, _>>()?;
+            if numbers.len() < count {
+                return Err(BptError::UnexpectedEof);
+            }
+            Ok((line, numbers))
+        };
+
+        let (_, count) = next_numbers(1)?;
+        let mut patches = vec![];
+        for _ in 0..count[0] as usize {
+            let (line, degree) = next_numbers(2)?;
+            let (u, v) = (degree[0] as usize, degree[1] as usize);
+            if (u, v) != (3, 3) {
+                return Err(BptError::UnsupportedDegree { line, u, v });
+            }
+            let mut points: Vec<Point> = Vec::with_capacity(16);
+            for _ in 0..16 {
+                let (_, c) = next_numbers(3)?;
+                points.push(point(c[0], c[1], c[2]));
+            }
+            patches.push(BezierPatch::new(points.try_into().unwrap()));
+        }
+        Ok(BptFile { patches })
+    }
+
+    /// A group containing a shape for each patch
+    pub fn to_group(
+        &self,
+        intersection: PatchIntersection,
+        material: &Material,
+        transform: &Transformation,
+    ) -> Shape {
+        let patches = self
+            .patches
+            .iter()
+            .map(|patch| {
+                Shape::new_bezier_patch(
+                    patch.clone(),
+                    intersection,
+                    material.clone(),
+                    Transformation::identity(),
+                )
+            })
+            .collect();
+        Shape::new_group(patches, transform.clone())
+    }
+}
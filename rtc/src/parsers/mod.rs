@@ -0,0 +1,9 @@
+pub use bpt::*;
+pub use netpbm::*;
+pub use obj::*;
+
+mod bpt;
+mod netpbm;
+mod obj;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,182 @@
+//! Parser for the Netpbm image formats PGM (P2, P5) and PPM (P3, P6), and for the floating
+//! point PFM format (PF, Pf) used for HDR images
+
+use std::{fmt, fs, io, path::Path};
+
+use crate::primitives::{canvas::Canvas, pixel::Pixel};
+
+#[derive(Debug)]
+pub enum NetpbmError {
+    Io(io::Error),
+    /// The magic number isn't one of P2, P3, P5, P6, PF or Pf
+    UnsupportedFormat(String),
+    /// The file ended before all pixels were read
+    UnexpectedEof,
+    /// A number in the header or the ASCII pixel data couldn't be parsed
+    MalformedNumber(String),
+    /// The maximum value has to be between 1 and 65535
+    InvalidMaxValue(u32),
+}
+
+impl fmt::Display for NetpbmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetpbmError::Io(e) => write!(f, "{}", e),
+            NetpbmError::UnsupportedFormat(s) => write!(f, "unsupported image format `{}`", s),
+            NetpbmError::UnexpectedEof => write!(f, "unexpected end of image data"),
+            NetpbmError::MalformedNumber(s) => write!(f, "malformed number `{}`", s),
+            NetpbmError::InvalidMaxValue(v) => write!(f, "invalid maximum value {}", v),
+        }
+    }
+}
+
+impl From<io::Error> for NetpbmError {
+    fn from(e: io::Error) -> Self {
+        NetpbmError::Io(e)
+    }
+}
+
+/// Read a PGM, PPM or PFM image from a file
+pub fn read_netpbm(path: impl AsRef<Path>) -> Result<Canvas, NetpbmError> {
+    parse_netpbm(&fs::read(path)?)
+}
+
+/// Parse a PGM, PPM or PFM image. Grayscale images yield pixels with equal channels. The values
+/// of PGM and PPM images are normalized to the range from 0 to 1, those of PFM images are kept
+/// as they are and may exceed 1.
+pub fn parse_netpbm(data: &[u8]) -> Result<Canvas, NetpbmError> {
+    let mut reader = Reader { data, pos: 0 };
+    let magic = reader.token()?;
+    let (channels, binary) = match magic.as_str() {
+        "P2" => (1, false),
+        "P3" => (3, false),
+        "P5" => (1, true),
+        "P6" => (3, true),
+        "Pf" => return parse_pfm(reader, 1),
+        "PF" => return parse_pfm(reader, 3),
+        _ => return Err(NetpbmError::UnsupportedFormat(magic)),
+    };
+    let width = reader.number()? as usize;
+    let height = reader.number()? as usize;
+    let max_value = reader.number()?;
+    if max_value == 0 || max_value > 65535 {
+        return Err(NetpbmError::InvalidMaxValue(max_value));
+    }
+    if binary {
+        // exactly one whitespace character separates the header from the data
+        reader.pos += 1;
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    let mut sample = || -> Result<f32, NetpbmError> {
+        let value = if !binary {
+            reader.number()?
+        } else if max_value < 256 {
+            reader.byte()? as u32
+        } else {
+            (reader.byte()? as u32) << 8 | reader.byte()? as u32
+        };
+        Ok(value as f32 / max_value as f32)
+    };
+    for i in 0..height {
+        for j in 0..width {
+            canvas[(i, j)] = if channels == 1 {
+                let v = sample()?;
+                Pixel::new_rgb(v, v, v)
+            } else {
+                Pixel::new_rgb(sample()?, sample()?, sample()?)
+            };
+        }
+    }
+    Ok(canvas)
+}
+
+/// Parse the rest of a PFM image after its magic number
+fn parse_pfm(mut reader: Reader, channels: usize) -> Result<Canvas, NetpbmError> {
+    let width = reader.number()? as usize;
+    let height = reader.number()? as usize;
+    // the sign of the scale gives the byte order, negative for little endian
+    let little_endian = reader.float()? < 0.;
+    reader.pos += 1;
+
+    let mut canvas = Canvas::new(width, height);
+    let mut sample = || -> Result<f32, NetpbmError> {
+        let bytes = [
+            reader.byte()?,
+            reader.byte()?,
+            reader.byte()?,
+            reader.byte()?,
+        ];
+        Ok(if little_endian {
+            f32::from_le_bytes(bytes)
+        } else {
+            f32::from_be_bytes(bytes)
+        })
+    };
+    // rows are stored from the bottom to the top
+    for i in (0..height).rev() {
+        for j in 0..width {
+            canvas[(i, j)] = if channels == 1 {
+                let v = sample()?;
+                Pixel::new_rgb(v, v, v)
+            } else {
+                Pixel::new_rgb(sample()?, sample()?, sample()?)
+            };
+        }
+    }
+    Ok(canvas)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, NetpbmError> {
+        let b = *self.data.get(self.pos).ok_or(NetpbmError::UnexpectedEof)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    /// The next whitespace separated token, skipping comments
+    fn token(&mut self) -> Result<String, NetpbmError> {
+        loop {
+            match self.data.get(self.pos) {
+                Some(b'#') => {
+                    while let Some(&b) = self.data.get(self.pos) {
+                        self.pos += 1;
+                        if b == b'\n' {
+                            break;
+                        }
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(_) => break,
+                None => return Err(NetpbmError::UnexpectedEof),
+            }
+        }
+        let start = self.pos;
+        while let Some(b) = self.data.get(self.pos) {
+            if b.is_ascii_whitespace() || *b == b'#' {
+                break;
+            }
+            self.pos += 1;
+        }
+        Ok(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
+
+    fn number(&mut self) -> Result<u32, NetpbmError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| NetpbmError::MalformedNumber(token))
+    }
+
+    fn float(&mut self) -> Result<f64, NetpbmError> {
+        let token = self.token()?;
+        token
+            .parse()
+            .map_err(|_| NetpbmError::MalformedNumber(token))
+    }
+}
@@ -0,0 +1,265 @@
+//! Parser for Wavefront OBJ files
+
+use std::{fmt, fs, io, path::Path};
+
+use crate::{
+    primitives::vector::{point, vector, Point, Transformation, Vec3D},
+    shading::Material,
+    shapes::{Shape, SmoothTriangle, Triangle},
+};
+
+/// A single corner of a face referencing the parsed data by (zero based) index
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjVertex {
+    pub vertex: usize,
+    pub texture_coord: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+/// A named group of faces, faces are already triangulated
+#[derive(Clone, Debug, Default)]
+pub struct ObjGroup {
+    pub name: String,
+    pub triangles: Vec<[ObjVertex; 3]>,
+}
+
+/// Problems encountered while parsing a file. The offending line is skipped.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjWarningKind {
+    /// The statement isn't supported and was ignored
+    UnknownStatement(String),
+    /// A number couldn't be parsed
+    MalformedNumber(String),
+    /// A statement had less arguments than required
+    MissingArguments { expected: usize, found: usize },
+    /// A face referenced an element that doesn't exist (yet)
+    IndexOutOfRange(String),
+    /// A face with less than three vertices
+    DegenerateFace,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjWarning {
+    /// Line number, starting at 1
+    pub line: usize,
+    pub kind: ObjWarningKind,
+}
+
+impl fmt::Display for ObjWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ObjWarningKind::UnknownStatement(s) => write!(f, "ignored unknown statement `{}`", s),
+            ObjWarningKind::MalformedNumber(s) => write!(f, "malformed number `{}`", s),
+            ObjWarningKind::MissingArguments { expected, found } => write!(
+                f,
+                "expected at least {} arguments but found {}",
+                expected, found
+            ),
+            ObjWarningKind::IndexOutOfRange(s) => write!(f, "index out of range in `{}`", s),
+            ObjWarningKind::DegenerateFace => write!(f, "face with less than three vertices"),
+        }
+    }
+}
+
+/// The contents of an OBJ file
+#[derive(Clone, Debug)]
+pub struct ObjFile {
+    pub vertices: Vec<Point>,
+    pub normals: Vec<Vec3D>,
+    pub texture_coords: Vec<(f64, f64)>,
+    /// The first group is the default group collecting all faces that appear before
+    /// the first named group
+    pub groups: Vec<ObjGroup>,
+    pub warnings: Vec<ObjWarning>,
+}
+
+impl ObjFile {
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse the contents of an OBJ file. Lines that can't be understood are
+    /// recorded in `warnings` and otherwise ignored.
+    pub fn parse(input: &str) -> Self {
+        let mut obj = ObjFile {
+            vertices: vec![],
+            normals: vec![],
+            texture_coords: vec![],
+            groups: vec![ObjGroup::default()],
+            warnings: vec![],
+        };
+        for (i, line) in input.lines().enumerate() {
+            // everything following a # is a comment
+            let line = line.split('#').next().unwrap_or("");
+            let mut tokens = line.split_whitespace();
+            let statement = match tokens.next() {
+                Some(s) => s,
+                None => continue,
+            };
+            let args = tokens.collect::<Vec<_>>();
+            if let Err(kind) = obj.parse_statement(statement, &args) {
+                obj.warnings.push(ObjWarning { line: i + 1, kind });
+            }
+        }
+        obj
+    }
+
+    fn parse_statement(&mut self, statement: &str, args: &[&str]) -> Result<(), ObjWarningKind> {
+        match statement {
+            "v" => {
+                let v = parse_floats(args, 3, 3)?;
+                self.vertices.push(point(v[0], v[1], v[2]));
+            }
+            "vn" => {
+                let n = parse_floats(args, 3, 3)?;
+                self.normals.push(vector(n[0], n[1], n[2]));
+            }
+            "vt" => {
+                let uv = parse_floats(args, 2, 1)?;
+                self.texture_coords.push((uv[0], uv[1]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(ObjWarningKind::DegenerateFace);
+                }
+                let vertices = args
+                    .iter()
+                    .map(|arg| self.parse_face_vertex(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                // fan triangulation, only valid for convex polygons
+                let group = self.groups.last_mut().unwrap();
+                for i in 1..vertices.len() - 1 {
+                    group
+                        .triangles
+                        .push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                let name = args.join(" ");
+                self.groups.push(ObjGroup {
+                    name,
+                    triangles: vec![],
+                });
+            }
+            _ => return Err(ObjWarningKind::UnknownStatement(statement.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Parse a face vertex of the forms `v`, `v/vt`, `v//vn` or `v/vt/vn`
+    fn parse_face_vertex(&self, arg: &str) -> Result<ObjVertex, ObjWarningKind> {
+        let mut parts = arg.split('/');
+        let vertex = parts.next().unwrap_or("");
+        let texture_coord = parts.next().filter(|s| !s.is_empty());
+        let normal = parts.next().filter(|s| !s.is_empty());
+        Ok(ObjVertex {
+            vertex: resolve_index(vertex, self.vertices.len())?,
+            texture_coord: texture_coord
+                .map(|s| resolve_index(s, self.texture_coords.len()))
+                .transpose()?,
+            normal: normal
+                .map(|s| resolve_index(s, self.normals.len()))
+                .transpose()?,
+        })
+    }
+
+    /// Total number of triangles over all groups
+    pub fn triangle_count(&self) -> usize {
+        self.groups.iter().map(|g| g.triangles.len()).sum()
+    }
+
+    /// Create a triangle for every face of the given group. Faces where all vertices have
+    /// normals become smooth triangles, texture coordinates are kept if all vertices have them.
+    pub fn group_shapes(
+        &self,
+        group: &ObjGroup,
+        material: &Material,
+        transform: &Transformation,
+    ) -> Vec<Shape> {
+        group
+            .triangles
+            .iter()
+            .map(|[a, b, c]| {
+                let p = |v: &ObjVertex| self.vertices[v.vertex].clone();
+                let mut triangle = Triangle::new(p(a), p(b), p(c));
+                if let (Some(t1), Some(t2), Some(t3)) =
+                    (a.texture_coord, b.texture_coord, c.texture_coord)
+                {
+                    triangle.texture_coords = Some([
+                        self.texture_coords[t1],
+                        self.texture_coords[t2],
+                        self.texture_coords[t3],
+                    ]);
+                }
+                match (a.normal, b.normal, c.normal) {
+                    (Some(n1), Some(n2), Some(n3)) => Shape::new(
+                        SmoothTriangle {
+                            triangle,
+                            n1: self.normals[n1].clone(),
+                            n2: self.normals[n2].clone(),
+                            n3: self.normals[n3].clone(),
+                        },
+                        material.clone(),
+                        transform.clone(),
+                    ),
+                    _ => Shape::new(triangle, material.clone(), transform.clone()),
+                }
+            })
+            .collect()
+    }
+
+    /// Create a group containing all triangles. Named groups of the file become subgroups.
+    pub fn to_group(&self, material: &Material, transform: &Transformation) -> Shape {
+        let identity = Transformation::identity();
+        let mut children = vec![];
+        for (i, group) in self.groups.iter().enumerate() {
+            let triangles = self.group_shapes(group, material, &identity);
+            if i == 0 {
+                children.extend(triangles);
+            } else {
+                children.push(Shape::new_group(triangles, identity.clone()));
+            }
+        }
+        Shape::new_group(children, transform.clone())
+    }
+
+    /// Create the triangles of all groups so they can be passed to `World::new`
+    pub fn shapes(&self, material: &Material, transform: &Transformation) -> Vec<Shape> {
+        self.groups
+            .iter()
+            .flat_map(|group| self.group_shapes(group, material, transform))
+            .collect()
+    }
+}
+
+/// Parse the first `count` arguments as floats. Missing optional arguments (those past
+/// `required`) default to zero.
+fn parse_floats(args: &[&str], count: usize, required: usize) -> Result<Vec<f64>, ObjWarningKind> {
+    if args.len() < required {
+        return Err(ObjWarningKind::MissingArguments {
+            expected: required,
+            found: args.len(),
+        });
+    }
+    let mut out = vec![0.; count];
+    for (o, arg) in out.iter_mut().zip(args.iter()) {
+        *o = arg
+            .parse()
+            .map_err(|_| ObjWarningKind::MalformedNumber(arg.to_string()))?;
+    }
+    Ok(out)
+}
+
+/// Convert a one based (or negative, relative to the end) OBJ index into a zero based one
+fn resolve_index(s: &str, count: usize) -> Result<usize, ObjWarningKind> {
+    let i = s
+        .parse::<isize>()
+        .map_err(|_| ObjWarningKind::MalformedNumber(s.to_string()))?;
+    let resolved = if i < 0 { count as isize + i } else { i - 1 };
+    if 0 <= resolved && (resolved as usize) < count {
+        Ok(resolved as usize)
+    } else {
+        Err(ObjWarningKind::IndexOutOfRange(s.to_string()))
+    }
+}
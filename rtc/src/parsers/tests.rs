@@ -0,0 +1,337 @@
+use super::*;
+
+use crate::{
+    assert_approx_eq,
+    primitives::{
+        canvas::Canvas,
+        pixel::Pixel,
+        vector::{point, vector, Transformation},
+    },
+    shading::Material,
+    shapes::{PatchIntersection, SmoothTriangle, Triangle},
+};
+
+#[test]
+fn obj_ignores_unrecognized_lines() {
+    let input = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+    let obj = ObjFile::parse(input);
+    assert_eq!(obj.warnings.len(), 5);
+    assert_eq!(
+        obj.warnings[0],
+        ObjWarning {
+            line: 1,
+            kind: ObjWarningKind::UnknownStatement("There".to_string())
+        }
+    );
+    assert_eq!(obj.triangle_count(), 0);
+}
+
+#[test]
+fn obj_vertex_records() {
+    let input = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+    let obj = ObjFile::parse(input);
+    assert!(obj.warnings.is_empty());
+    assert_approx_eq!(obj.vertices[0], &point(-1., 1., 0.));
+    assert_approx_eq!(obj.vertices[1], &point(-1., 0.5, 0.));
+    assert_approx_eq!(obj.vertices[2], &point(1., 0., 0.));
+    assert_approx_eq!(obj.vertices[3], &point(1., 1., 0.));
+}
+
+#[test]
+fn obj_triangle_faces() {
+    let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+    let obj = ObjFile::parse(input);
+    let g = &obj.groups[0];
+    assert_eq!(g.triangles.len(), 2);
+    let indices = |t: &[ObjVertex; 3]| [t[0].vertex, t[1].vertex, t[2].vertex];
+    assert_eq!(indices(&g.triangles[0]), [0, 1, 2]);
+    assert_eq!(indices(&g.triangles[1]), [0, 2, 3]);
+}
+
+#[test]
+fn obj_triangulate_polygons() {
+    let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+    let obj = ObjFile::parse(input);
+    let g = &obj.groups[0];
+    assert_eq!(g.triangles.len(), 3);
+    let indices = |t: &[ObjVertex; 3]| [t[0].vertex, t[1].vertex, t[2].vertex];
+    assert_eq!(indices(&g.triangles[0]), [0, 1, 2]);
+    assert_eq!(indices(&g.triangles[1]), [0, 2, 3]);
+    assert_eq!(indices(&g.triangles[2]), [0, 3, 4]);
+}
+
+#[test]
+fn obj_named_groups() {
+    let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+    let obj = ObjFile::parse(input);
+    assert_eq!(obj.groups.len(), 3);
+    assert!(obj.groups[0].triangles.is_empty());
+    assert_eq!(obj.groups[1].name, "FirstGroup");
+    assert_eq!(obj.groups[1].triangles.len(), 1);
+    assert_eq!(obj.groups[2].name, "SecondGroup");
+    assert_eq!(obj.groups[2].triangles.len(), 1);
+    let shapes = obj.shapes(&Material::default(), &Transformation::identity());
+    assert_eq!(shapes.len(), 2);
+    let group = obj.to_group(&Material::default(), &Transformation::identity());
+    assert_eq!(group.children().len(), 2);
+    assert_eq!(group.children()[0].children().len(), 1);
+    assert_eq!(group.children()[1].children().len(), 1);
+}
+
+#[test]
+fn obj_vertex_normals_and_texture_coords() {
+    let input = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3
+vt 0.5 0.25";
+    let obj = ObjFile::parse(input);
+    assert_approx_eq!(obj.normals[0], &vector(0., 0., 1.));
+    assert_approx_eq!(obj.normals[1], &vector(0.707, 0., -0.707));
+    assert_approx_eq!(obj.normals[2], &vector(1., 2., 3.));
+    assert_eq!(obj.texture_coords, vec![(0.5, 0.25)]);
+}
+
+#[test]
+fn obj_faces_with_normals() {
+    let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/0/3 2/102/1 3/14/2
+f -3//-1 -2//-3 -1//-2";
+    let obj = ObjFile::parse(input);
+    // the second face references texture coordinates that don't exist
+    assert_eq!(obj.warnings.len(), 1);
+    assert_eq!(obj.warnings[0].line, 10);
+    let g = &obj.groups[0];
+    assert_eq!(g.triangles.len(), 2);
+    let expected = ObjVertex {
+        vertex: 0,
+        texture_coord: None,
+        normal: Some(2),
+    };
+    assert_eq!(g.triangles[0][0], expected);
+    assert_eq!(g.triangles[0], g.triangles[1]);
+}
+
+#[test]
+fn obj_faces_keep_texture_coords() {
+    let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+vt 0.5 1
+vt 0 0
+vt 1 0
+vn 0 0 -1
+
+f 1/1 2/2 3/3
+f 1/1/1 2/2/1 3/3/1
+f 1 2 3";
+    let obj = ObjFile::parse(input);
+    let shapes = obj.shapes(&Material::default(), &Transformation::identity());
+    let expected = Some([(0.5, 1.), (0., 0.), (1., 0.)]);
+    let triangle = shapes[0].geometry_as::<Triangle>().unwrap();
+    assert_eq!(triangle.texture_coords, expected);
+    let smooth = shapes[1].geometry_as::<SmoothTriangle>().unwrap();
+    assert_eq!(smooth.triangle.texture_coords, expected);
+    let triangle = shapes[2].geometry_as::<Triangle>().unwrap();
+    assert_eq!(triangle.texture_coords, None);
+}
+
+#[test]
+fn obj_malformed_lines_are_reported() {
+    let input = "v 1 2
+v 1 a 3
+f 1 2";
+    let obj = ObjFile::parse(input);
+    let kinds = obj.warnings.into_iter().map(|w| w.kind).collect::<Vec<_>>();
+    assert_eq!(
+        kinds,
+        vec![
+            ObjWarningKind::MissingArguments {
+                expected: 3,
+                found: 2
+            },
+            ObjWarningKind::MalformedNumber("a".to_string()),
+            ObjWarningKind::DegenerateFace,
+        ]
+    );
+}
+
+#[test]
+fn netpbm_ascii_graymap() {
+    let input = "P2
+# a comment
+3 2 # trailing comment
+4
+0 1 2
+3 4 2
+";
+    let c = parse_netpbm(input.as_bytes()).unwrap();
+    assert_eq!(c.width(), 3);
+    assert_eq!(c.height(), 2);
+    assert_approx_eq!(c[(0, 1)].r, 0.25);
+    assert_approx_eq!(c[(0, 1)].b, 0.25);
+    assert_approx_eq!(c[(1, 1)].g, 1.);
+    assert_approx_eq!(c[(1, 2)].r, 0.5);
+}
+
+#[test]
+fn netpbm_reads_written_ppm() {
+    let mut canvas = Canvas::new(2, 2);
+    canvas[(0, 0)] = Pixel::white();
+    canvas[(0, 1)] = Pixel::red();
+    canvas[(1, 0)] = Pixel::new_rgb(0.2, 0.4, 0.6);
+    let c = parse_netpbm(canvas.as_ppm().as_bytes()).unwrap();
+    assert_eq!(c.width(), 2);
+    assert_eq!(c.height(), 2);
+    for (a, b) in c.iter().zip(canvas.iter()) {
+        assert_approx_eq!(a.r, b.r);
+        assert_approx_eq!(a.g, b.g);
+        assert_approx_eq!(a.b, b.b);
+    }
+}
+
+#[test]
+fn netpbm_binary() {
+    let mut input = b"P5\n2 1\n255\n".to_vec();
+    input.extend_from_slice(&[0, 255]);
+    let c = parse_netpbm(&input).unwrap();
+    assert_approx_eq!(c[(0, 0)].r, 0.);
+    assert_approx_eq!(c[(0, 1)].r, 1.);
+
+    let mut input = b"P6 1 1 65535\n".to_vec();
+    input.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+    let c = parse_netpbm(&input).unwrap();
+    assert_approx_eq!(c[(0, 0)].r, 1.);
+    assert_approx_eq!(c[(0, 0)].g, 0.5);
+    assert_approx_eq!(c[(0, 0)].b, 0.);
+}
+
+#[test]
+fn netpbm_errors() {
+    assert!(matches!(
+        parse_netpbm(b"P4\n1 1\n"),
+        Err(NetpbmError::UnsupportedFormat(_))
+    ));
+    assert!(matches!(
+        parse_netpbm(b"P2\n2 2\n255\n0 0 0"),
+        Err(NetpbmError::UnexpectedEof)
+    ));
+    assert!(matches!(
+        parse_netpbm(b"P2\n2 x\n255\n"),
+        Err(NetpbmError::MalformedNumber(_))
+    ));
+    assert!(matches!(
+        parse_netpbm(b"P2\n1 1\n0\n0"),
+        Err(NetpbmError::InvalidMaxValue(0))
+    ));
+}
+
+#[test]
+fn netpbm_pfm() {
+    let mut input = b"PF\n2 1\n-1.0\n".to_vec();
+    for v in &[0.5f32, 2., 8., 0., 0.25, 1.] {
+        input.extend_from_slice(&v.to_le_bytes());
+    }
+    let c = parse_netpbm(&input).unwrap();
+    assert_eq!(c.width(), 2);
+    assert_eq!(c.height(), 1);
+    assert_eq!(c[(0, 0)], Pixel::new_rgb(0.5, 2., 8.));
+    assert_eq!(c[(0, 1)], Pixel::new_rgb(0., 0.25, 1.));
+
+    // big endian grayscale, stored from the bottom row up
+    let mut input = b"Pf\n1 2\n1.0\n".to_vec();
+    for v in &[3f32, 4.] {
+        input.extend_from_slice(&v.to_be_bytes());
+    }
+    let c = parse_netpbm(&input).unwrap();
+    assert_eq!(c[(0, 0)], Pixel::new_rgb(4., 4., 4.));
+    assert_eq!(c[(1, 0)], Pixel::new_rgb(3., 3., 3.));
+
+    assert!(matches!(
+        parse_netpbm(b"PF\n1 1\nscale\n"),
+        Err(NetpbmError::MalformedNumber(_))
+    ));
+    assert!(matches!(
+        parse_netpbm(b"PF\n1 1\n-1.0\n\0\0"),
+        Err(NetpbmError::UnexpectedEof)
+    ));
+}
+
+fn bpt_patch(degree: &str) -> String {
+    let mut s = format!("{}\n", degree);
+    for i in 0..4 {
+        for j in 0..4 {
+            s += &format!("{} {} 0\n", i, j);
+        }
+    }
+    s
+}
+
+#[test]
+fn bpt_patches() {
+    let input = format!("2\n{}\n{}", bpt_patch("3 3"), bpt_patch("3 3"));
+    let bpt = BptFile::parse(&input).unwrap();
+    assert_eq!(bpt.patches.len(), 2);
+    assert_approx_eq!(bpt.patches[1].control_points[0], &point(0., 0., 0.));
+    assert_approx_eq!(bpt.patches[1].control_points[6], &point(1., 2., 0.));
+    let g = bpt.to_group(
+        PatchIntersection::Tessellated { tolerance: 1e-3 },
+        &Material::default(),
+        &Transformation::identity(),
+    );
+    assert_eq!(g.children().len(), 2);
+}
+
+#[test]
+fn bpt_errors() {
+    assert!(matches!(
+        BptFile::parse(&format!("1\n{}", bpt_patch("2 3"))),
+        Err(BptError::UnsupportedDegree {
+            line: 2,
+            u: 2,
+            v: 3
+        })
+    ));
+    assert!(matches!(
+        BptFile::parse(&format!("2\n{}", bpt_patch("3 3"))),
+        Err(BptError::UnexpectedEof)
+    ));
+    assert!(matches!(
+        BptFile::parse("1\n3 3\n0 0 zero"),
+        Err(BptError::MalformedNumber { line: 3, .. })
+    ));
+}
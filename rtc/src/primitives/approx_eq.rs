@@ -77,6 +77,8 @@ macro_rules! assert_approx_eq {
 }
 
 #[cfg(test)]
+// the tests compare with literals beyond the precision of floats on purpose
+#[allow(clippy::excessive_precision)]
 mod tests {
     use super::*;
     #[test]
@@ -1,6 +1,7 @@
 pub mod approx_eq;
 pub mod canvas;
 pub mod pixel;
+pub mod polynomial;
 pub mod ray;
 pub mod rendering;
 pub mod tmatrix;
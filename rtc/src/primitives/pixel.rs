@@ -133,6 +133,8 @@ mod tests {
 
     use crate::assert_approx_eq;
 
+    // the channels differ below the precision of a float on purpose
+    #[allow(clippy::excessive_precision)]
     #[test]
     fn approx_eq() {
         assert!(!Pixel::white().approx_eq(Pixel::red()));
@@ -0,0 +1,221 @@
+//! Real roots of polynomials up to degree four. All solvers return the roots in ascending order;
+//! repeated roots may show up multiple times.
+
+use std::f64::consts::PI;
+
+/// Coefficients smaller than this are treated as zero
+const COEFF_EPSILON: f64 = 1e-12;
+/// Number of newton iterations used to polish the roots of a quartic
+const POLISH_ITERATIONS: usize = 4;
+
+fn sorted(mut roots: Vec<f64>) -> Vec<f64> {
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots
+}
+
+/// Real roots of a*x + b
+pub fn solve_linear(a: f64, b: f64) -> Vec<f64> {
+    if a.abs() < COEFF_EPSILON {
+        vec![]
+    } else {
+        vec![-b / a]
+    }
+}
+
+/// Real roots of a*x^2 + b*x + c
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a.abs() < COEFF_EPSILON {
+        return solve_linear(b, c);
+    }
+    let disc = b * b - 4. * a * c;
+    if disc < 0. {
+        vec![]
+    } else if disc == 0. {
+        vec![-b / (2. * a)]
+    } else {
+        // Avoids the cancellation of -b + sqrt(disc) for b close to sqrt(disc)
+        let q = -0.5 * (b + b.signum() * disc.sqrt());
+        if q == 0. {
+            // b == 0 and c == 0
+            vec![0., 0.]
+        } else {
+            sorted(vec![q / a, c / q])
+        }
+    }
+}
+
+/// Real roots of a*x^3 + b*x^2 + c*x + d
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a.abs() < COEFF_EPSILON {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+    let q = (b * b - 3. * c) / 9.;
+    let r = (2. * b.powi(3) - 9. * b * c + 27. * d) / 54.;
+    let shift = b / 3.;
+    if r * r < q.powi(3) {
+        // three distinct real roots
+        let theta = (r / q.powi(3).sqrt()).acos();
+        let f = -2. * q.sqrt();
+        sorted(vec![
+            f * (theta / 3.).cos() - shift,
+            f * ((theta + 2. * PI) / 3.).cos() - shift,
+            f * ((theta - 2. * PI) / 3.).cos() - shift,
+        ])
+    } else {
+        let s = -r.signum() * (r.abs() + (r * r - q.powi(3)).sqrt()).cbrt();
+        let t = if s == 0. { 0. } else { q / s };
+        let mut roots = vec![s + t - shift];
+        if (s - t).abs() < COEFF_EPSILON.sqrt() * s.abs().max(1.) {
+            // the remaining complex roots coincide on the real axis
+            roots.push(-(s + t) / 2. - shift);
+        }
+        sorted(roots)
+    }
+}
+
+/// Real roots of a*x^4 + b*x^3 + c*x^2 + d*x + e using Ferrari's method. The roots are polished
+/// by a few Newton iterations on the original polynomial. The quartic only degenerates to a cubic
+/// if `a` vanishes next to the largest coefficient, a uniformly scaled polynomial has the same
+/// roots.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    let largest = [b, c, d, e].iter().fold(0., |m: f64, x| m.max(x.abs()));
+    if a.abs() <= f64::EPSILON * largest {
+        return solve_cubic(b, c, d, e);
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    // substitute x = y - b/4 to get the depressed quartic y^4 + p*y^2 + q*y + r
+    let shift = b / 4.;
+    let b2 = b * b;
+    let p = c - 3. / 8. * b2;
+    let q = d - b * c / 2. + b2 * b / 8.;
+    let r = e - b * d / 4. + b2 * c / 16. - 3. / 256. * b2 * b2;
+
+    let ys = if q.abs() < COEFF_EPSILON {
+        // biquadratic
+        solve_quadratic(1., p, r)
+            .into_iter()
+            .filter(|&z| z >= -COEFF_EPSILON)
+            .flat_map(|z| {
+                let y = z.max(0.).sqrt();
+                vec![-y, y]
+            })
+            .collect()
+    } else {
+        // Any positive root m of the resolvent cubic splits the quartic into two quadratics
+        let m = solve_cubic(1., p, p * p / 4. - r, -q * q / 8.)
+            .into_iter()
+            .fold(0., f64::max);
+        if m <= 0. {
+            vec![]
+        } else {
+            let s = (2. * m).sqrt();
+            let mut ys = solve_quadratic(1., -s, p / 2. + m + q / (2. * s));
+            ys.extend(solve_quadratic(1., s, p / 2. + m - q / (2. * s)));
+            ys
+        }
+    };
+
+    let f = |x: f64| (((x + b) * x + c) * x + d) * x + e;
+    let df = |x: f64| ((4. * x + 3. * b) * x + 2. * c) * x + d;
+    sorted(
+        ys.into_iter()
+            .map(|y| {
+                let mut x = y - shift;
+                for _ in 0..POLISH_ITERATIONS {
+                    let slope = df(x);
+                    if slope == 0. {
+                        break;
+                    }
+                    let next = x - f(x) / slope;
+                    // only accept improvements, newton may diverge close to multiple roots
+                    if f(next).abs() >= f(x).abs() {
+                        break;
+                    }
+                    x = next;
+                }
+                x
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives::approx_eq::ApproxEq;
+
+    fn assert_roots(roots: Vec<f64>, expected: Vec<f64>) {
+        assert_eq!(roots.len(), expected.len(), "{:?} != {:?}", roots, expected);
+        for (root, exp) in roots.into_iter().zip(expected) {
+            assert!(root.approx_eq(exp), "{} != {}", root, exp);
+        }
+    }
+
+    #[test]
+    fn quadratic() {
+        assert_roots(solve_quadratic(1., -3., 2.), vec![1., 2.]);
+        assert_roots(solve_quadratic(1., 0., 1.), vec![]);
+        assert_roots(solve_quadratic(1., -2., 1.), vec![1.]);
+        assert_roots(solve_quadratic(0., 2., -1.), vec![0.5]);
+        // catastrophic cancellation with the textbook formula
+        assert_roots(solve_quadratic(1., -1e8, 1.), vec![1e-8, 1e8]);
+    }
+
+    #[test]
+    fn cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic(1., -6., 11., -6.), vec![1., 2., 3.]);
+        // (x - 1)(x^2 + 1)
+        assert_roots(solve_cubic(1., -1., 1., -1.), vec![1.]);
+        // (x - 1)(x - 1)(x + 2)
+        assert_roots(solve_cubic(1., 0., -3., 2.), vec![-2., 1.]);
+        assert_roots(solve_cubic(2., -12., 22., -12.), vec![1., 2., 3.]);
+    }
+
+    #[test]
+    fn quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic(1., -10., 35., -50., 24.),
+            vec![1., 2., 3., 4.],
+        );
+        // (x^2 - 1)(x^2 - 4), biquadratic
+        assert_roots(solve_quartic(1., 0., -5., 0., 4.), vec![-2., -1., 1., 2.]);
+        // (x^2 + 1)(x^2 + 4)
+        assert_roots(solve_quartic(1., 0., 5., 0., 4.), vec![]);
+        // (x - 1)(x + 2)(x^2 + 1)
+        assert_roots(solve_quartic(1., 1., -1., 1., -2.), vec![-2., 1.]);
+        // falls back to the cubic
+        assert_roots(solve_quartic(0., 1., -6., 11., -6.), vec![1., 2., 3.]);
+    }
+
+    #[test]
+    fn quartic_small_coefficients() {
+        // (x - 1)(x - 2)(x - 3)(x - 4) scaled by 1e-14
+        let s = 1e-14;
+        assert_roots(
+            solve_quartic(s, -10. * s, 35. * s, -50. * s, 24. * s),
+            vec![1., 2., 3., 4.],
+        );
+    }
+
+    #[test]
+    fn quartic_widely_spread_roots() {
+        // (x - 0.001)(x - 0.5)(x - 40)(x - 1000)
+        let roots = [0.001, 0.5, 40., 1000.];
+        let e1: f64 = roots.iter().sum();
+        let e2 = roots[0] * roots[1]
+            + roots[0] * roots[2]
+            + roots[0] * roots[3]
+            + roots[1] * roots[2]
+            + roots[1] * roots[3]
+            + roots[2] * roots[3];
+        let e3 = roots[0] * roots[1] * roots[2]
+            + roots[0] * roots[1] * roots[3]
+            + roots[0] * roots[2] * roots[3]
+            + roots[1] * roots[2] * roots[3];
+        let e4: f64 = roots.iter().product();
+        assert_roots(solve_quartic(1., -e1, e2, -e3, e4), roots.to_vec());
+    }
+}
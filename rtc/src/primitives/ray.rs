@@ -4,11 +4,21 @@ use crate::primitives::vector::{Point, Transformation, Vec3D};
 pub struct Ray {
     pub origin: Point,
     pub direction: Vec3D,
+    /// The point in time at which the ray is cast, moving shapes are intersected at this time
+    pub time: f64,
 }
 
 impl Ray {
     pub fn new(origin: Point, direction: Vec3D) -> Self {
-        Ray { origin, direction }
+        Self::new_with_time(origin, direction, 0.)
+    }
+
+    pub fn new_with_time(origin: Point, direction: Vec3D, time: f64) -> Self {
+        Ray {
+            origin,
+            direction,
+            time,
+        }
     }
 
     pub fn position(&self, t: f64) -> Point {
@@ -16,9 +26,10 @@ impl Ray {
     }
 
     pub fn transform(&self, transformation: &Transformation) -> Self {
-        Self::new(
+        Self::new_with_time(
             transformation * &self.origin,
             transformation * &self.direction,
+            self.time,
         )
     }
 }
@@ -66,4 +77,12 @@ mod tests {
         assert_approx_eq!(r2.origin, &point(2., 6., 12.));
         assert_approx_eq!(r2.direction, &vector(0., 3., 0.));
     }
+
+    #[test]
+    fn transform_keeps_time() {
+        let r = Ray::new_with_time(point(1., 2., 3.), vector(0., 1., 0.), 0.25);
+        let r2 = r.transform(&Transformation::new_translation(3., 4., 5.));
+        assert_approx_eq!(r2.time, 0.25);
+        assert_approx_eq!(Ray::new(point(0., 0., 0.), vector(1., 0., 0.)).time, 0.);
+    }
 }
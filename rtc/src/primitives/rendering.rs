@@ -16,7 +16,7 @@ impl Rendering {
     }
 
     pub fn save_to_file(&self) -> std::io::Result<()> {
-        let mut file = File::create(&format!("renders/{}.ppm", self.name))?;
+        let mut file = File::create(format!("renders/{}.ppm", self.name))?;
         file.write_all(self.canvas.as_ppm().as_bytes())?;
         Ok(())
     }
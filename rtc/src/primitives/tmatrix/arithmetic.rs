@@ -19,7 +19,7 @@ where
     fn add(self, other: Self) -> Self::Output {
         Matrix::from(
             self.into_iter()
-                .zip(other.into_iter())
+                .zip(other)
                 .map(|(l, r)| l + r)
                 .collect::<Vec<_>>(),
         )
@@ -55,7 +55,7 @@ where
     fn add(self, other: Matrix<T, M, N>) -> Self::Output {
         Matrix::from(
             self.iter()
-                .zip(other.into_iter())
+                .zip(other)
                 .map(|(l, r)| *l + r)
                 .collect::<Vec<_>>(),
         )
@@ -91,7 +91,7 @@ where
     fn sub(self, other: Self) -> Self::Output {
         Matrix::from(
             self.into_iter()
-                .zip(other.into_iter())
+                .zip(other)
                 .map(|(l, r)| l - r)
                 .collect::<Vec<_>>(),
         )
@@ -127,7 +127,7 @@ where
     fn sub(self, other: Matrix<T, M, N>) -> Self::Output {
         Matrix::from(
             self.iter()
-                .zip(other.into_iter())
+                .zip(other)
                 .map(|(l, r)| *l - r)
                 .collect::<Vec<_>>(),
         )
@@ -162,7 +162,7 @@ where
     fn add_assign(&mut self, other: Self) {
         *self = Matrix::from(
             self.iter()
-                .zip(other.into_iter())
+                .zip(other)
                 .map(|(l, r)| *l + r)
                 .collect::<Vec<_>>(),
         )
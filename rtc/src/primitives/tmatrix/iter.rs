@@ -5,6 +5,16 @@ use std::ops::{Index, IndexMut};
 use crate::utils::typelevel_nums::*;
 
 use super::prelude::*;
+/// Iterate over all elements deinitializing self
+impl<T, M: Nat, N: Nat> IntoIterator for Matrix<T, M, N> {
+    type Item = T;
+    type IntoIter = std::vec::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.data.into_iter()
+    }
+}
+
 // Indexing
 impl<T, M: Nat + Val, N: Nat + Val> Index<(usize, usize)> for Matrix<T, M, N> {
     type Output = T;
@@ -66,13 +76,7 @@ impl<T, M: Nat + Val, N: Nat + Val> Matrix<T, M, N> {
     pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, usize, &T)> {
         self.iter_rows()
             .enumerate()
-            .map(|(i, iter)| iter.enumerate().map(move |(j, x)| (i, j, x)))
-            .flatten()
-    }
-
-    /// Iterate over all elements deinitializing self
-    pub fn into_iter(self) -> impl Iterator<Item = T> {
-        self.data.into_iter()
+            .flat_map(|(i, iter)| iter.enumerate().map(move |(j, x)| (i, j, x)))
     }
 
     /// Iterate over the ith row of the matrix deinitializing self
@@ -107,7 +111,6 @@ where
     pub fn into_iter_indexed(self) -> impl Iterator<Item = (usize, usize, T)> {
         self.into_iter_rows()
             .enumerate()
-            .map(|(i, iter)| iter.enumerate().map(move |(j, x)| (i, j, x)))
-            .flatten()
+            .flat_map(|(i, iter)| iter.enumerate().map(move |(j, x)| (i, j, x)))
     }
 }
@@ -1,5 +1,9 @@
 pub use amath::*;
+// these only hold trait impls for now, re-exported so public items added there keep the
+// same paths as the rest of the module
+#[allow(unused_imports)]
 pub use arithmetic::*;
+#[allow(unused_imports)]
 pub use iter::*;
 pub use prelude::*;
 
@@ -23,10 +23,12 @@ pub struct Matrix<T, M: Nat, N: Nat> {
 /// Verifies that the number of values matches the size
 /// # Example
 /// ```
+/// # use rtc::{matrix, utils::typelevel_nums::*};
 /// let a = matrix![ N4, N3 =>
 ///     1   2  3;
 ///     4 {-5} 6;
-///     7   8  9_isize
+///     7   8  9;
+///     8   7  6_isize
 /// ];
 /// ```
 /// Creates a new 4x3 matrix of isizes
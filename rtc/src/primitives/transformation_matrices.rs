@@ -3,8 +3,6 @@
 
 use num_traits::{Float, Num};
 
-use std::mem::replace;
-
 use crate::{
     matrix,
     primitives::vector::{CrossProd, Point, Transformation, Vec3D},
@@ -150,21 +148,21 @@ where
     /// Translate in space
     pub fn translate(&mut self, x: T, y: T, z: T) -> &mut Self {
         let new = Matrix::new_translation(x, y, z) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Scale (in relation to the origin)
     pub fn scale(&mut self, x: T, y: T, z: T) -> &mut Self {
         let new = Matrix::new_scaling(x, y, z) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Shear: x in proportion to y, x in proportion to z
     pub fn shear(&mut self, xy: T, xz: T, yx: T, yz: T, zx: T, zy: T) -> &mut Self {
         let new = Matrix::new_shear(xy, xz, yx, yz, zx, zy) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 }
@@ -176,21 +174,21 @@ where
     /// Rotate around the x axis by r radians
     pub fn rotate_x(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_x_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Rotate around the y axis by r radians
     pub fn rotate_y(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_y_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Rotate around the x axis by z radians
     pub fn rotate_z(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_z_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 }
@@ -242,21 +240,21 @@ where
     /// Translate in space
     pub fn translate(&mut self, x: T, y: T, z: T) -> &mut Self {
         let new = Matrix::new_translation(x, y, z) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Scale (in relation to the origin)
     pub fn scale(&mut self, x: T, y: T, z: T) -> &mut Self {
         let new = Matrix::new_scaling(x, y, z) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Shear: x in proportion to y, x in proportion to z
     pub fn shear(&mut self, xy: T, xz: T, yx: T, yz: T, zx: T, zy: T) -> &mut Self {
         let new = Matrix::new_shear(xy, xz, yx, yz, zx, zy) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 }
@@ -268,21 +266,21 @@ where
     /// Rotate around the x axis by r radians
     pub fn rotate_x(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_x_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Rotate around the y axis by r radians
     pub fn rotate_y(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_y_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 
     /// Rotate around the x axis by z radians
     pub fn rotate_z(&mut self, r: T) -> &mut Self {
         let new = Matrix::new_z_rotation(r) * &*self;
-        replace(self, new);
+        *self = new;
         self
     }
 }
@@ -0,0 +1,99 @@
+use std::{f64::consts::PI, fmt, sync::Arc};
+
+use crate::{
+    primitives::{canvas::Canvas, vector::Vec3D},
+    shading::{cube_face_uv, Color, ImageTexture, UvMapping, Wrap},
+};
+
+/// Why an image can't be used as a background
+#[derive(Debug, Clone, PartialEq)]
+pub enum BackgroundError {
+    /// An image without any pixel
+    EmptyImage,
+}
+
+impl fmt::Display for BackgroundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackgroundError::EmptyImage => write!(f, "backgrounds need at least one pixel"),
+        }
+    }
+}
+
+/// What rays that don't hit any object see
+#[derive(Clone)]
+pub enum Background {
+    /// The same color in every direction
+    Color(Color),
+    /// A sky blending from `bottom` straight down to `top` straight up
+    Gradient { bottom: Color, top: Color },
+    /// A panorama in latitude/longitude layout, e.g. an HDR image read from a PFM file. Its center
+    /// lies in the direction of the negative z axis and its top row straight up. The image is
+    /// sampled with the filter and wrap of the texture, its mapping isn't used.
+    Equirectangular(ImageTexture),
+    /// Six square images on the faces of a cube around the scene, in the order left (-x),
+    /// front (+z), right (+x), back (-z), up (+y) and down (-y)
+    CubeMap(Arc<[ImageTexture; 6]>),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color(Color::black())
+    }
+}
+
+impl Background {
+    /// A panorama background, see `Background::Equirectangular`. Fails for an empty image.
+    pub fn equirectangular(image: Canvas) -> Result<Self, BackgroundError> {
+        Ok(Background::Equirectangular(texture(
+            image,
+            UvMapping::Spherical,
+        )?))
+    }
+
+    /// A cube map background, see `Background::CubeMap`. Fails if any of the faces is empty.
+    pub fn cube_map(faces: [Canvas; 6]) -> Result<Self, BackgroundError> {
+        let [left, front, right, back, up, down] = faces;
+        let face = |image| texture(image, UvMapping::Cubic);
+        Ok(Background::CubeMap(Arc::new([
+            face(left)?,
+            face(front)?,
+            face(right)?,
+            face(back)?,
+            face(up)?,
+            face(down)?,
+        ])))
+    }
+
+    /// The color seen in some direction
+    pub fn color_at(&self, direction: &Vec3D) -> Color {
+        match self {
+            Background::Color(color) => *color,
+            Background::Gradient { bottom, top } => {
+                let t = ((direction.clone().unit().y() + 1.) / 2.) as f32;
+                *bottom * (1. - t) + *top * t
+            }
+            Background::Equirectangular(image) => {
+                let d = direction.clone().unit();
+                let u = 0.5 + (-d.x()).atan2(-d.z()) / (2. * PI);
+                let v = 1. - d.y().clamp(-1., 1.).acos() / PI;
+                image.color_at_uv(u, v)
+            }
+            Background::CubeMap(faces) => {
+                let (face, u, v) = cube_face_uv(direction.x(), direction.y(), direction.z());
+                faces[face].color_at_uv(u, v)
+            }
+        }
+    }
+}
+
+/// A texture of a background image. The edge pixels continue at the poles of panoramas and at
+/// the edges of cube faces, rather than the opposite side of the image.
+fn texture(image: Canvas, mapping: UvMapping) -> Result<ImageTexture, BackgroundError> {
+    if image.width() == 0 || image.height() == 0 {
+        return Err(BackgroundError::EmptyImage);
+    }
+    let mut texture = ImageTexture::new(image, mapping);
+    texture.wrap = Wrap::Clamp;
+    Ok(texture)
+}
@@ -3,15 +3,29 @@ use std::{
     thread::spawn,
 };
 
-use crate::primitives::{
-    canvas::Canvas,
-    ray::Ray,
-    vector::{point, Point, Transformation},
+use crate::{
+    primitives::{
+        canvas::Canvas,
+        ray::Ray,
+        vector::{point, Point, Transformation},
+    },
+    shading::Color,
+    utils::random::Rng,
 };
 
 static MAXIMUM_REFLECTION_RECURSION_DEPTH: usize = 5;
 
-use super::World;
+use super::{PathTracer, World};
+
+/// How the colors seen along camera rays are computed
+#[derive(Debug, Clone, PartialEq)]
+pub enum Integrator {
+    /// Recursive ray tracing of direct light, mirror reflections and refractions
+    Whitted,
+    /// Monte Carlo path tracing with global illumination. It needs many samples per pixel to
+    /// converge, see `Camera::set_samples`.
+    PathTracer(PathTracer),
+}
 
 /// Virtual camera
 /// Virtual canvas is one unit in front of camera
@@ -29,6 +43,13 @@ pub struct Camera {
     pixel_size: f64,
     half_width: f64,
     half_height: f64,
+    /// Time at which the shutter opens
+    shutter_open: f64,
+    /// Time at which the shutter closes, rays are spread over the time in between
+    shutter_close: f64,
+    /// Number of rays cast per pixel
+    samples: usize,
+    integrator: Integrator,
 }
 
 impl Camera {
@@ -51,6 +72,10 @@ impl Camera {
             half_width,
             half_height,
             inverse_transform,
+            shutter_open: 0.,
+            shutter_close: 0.,
+            samples: 1,
+            integrator: Integrator::Whitted,
         }
     }
 
@@ -91,10 +116,50 @@ impl Camera {
         self.pixel_size
     }
 
+    /// The times at which the shutter opens and closes
+    pub fn shutter(&self) -> (f64, f64) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    /// Expose the image from `open` to `close`. Together with more than one sample per pixel
+    /// this blurs moving shapes.
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        assert!(open <= close, "The shutter has to open before it closes.");
+        self.shutter_open = open;
+        self.shutter_close = close;
+    }
+
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+
+    /// Set the number of rays cast per pixel, they're spread evenly over the shutter interval
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples.max(1);
+    }
+
+    pub fn integrator(&self) -> &Integrator {
+        &self.integrator
+    }
+
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.integrator = integrator;
+    }
+
     /// Calculate a ray through the coordinate pair (x, y) from the camera through the canvas
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        let x_offset = (x as f64 + 0.5) * self.pixel_size;
-        let y_offset = (y as f64 + 0.5) * self.pixel_size;
+        self.ray_for_pixel_at(x, y, self.shutter_open)
+    }
+
+    /// Calculate a ray through the coordinate pair (x, y) cast at some point in time
+    pub fn ray_for_pixel_at(&self, x: usize, y: usize, time: f64) -> Ray {
+        self.ray_through(x as f64 + 0.5, y as f64 + 0.5, time)
+    }
+
+    /// Calculate a ray through the canvas position (x, y) measured in pixels
+    fn ray_through(&self, x: f64, y: f64, time: f64) -> Ray {
+        let x_offset = x * self.pixel_size;
+        let y_offset = y * self.pixel_size;
 
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
@@ -103,7 +168,40 @@ impl Camera {
         let pixel = inverse * point(world_x, world_y, -1.);
         let origin = inverse * Point::origin();
         let direction = (pixel - &origin).unit();
-        Ray::new(origin, direction)
+        Ray::new_with_time(origin, direction, time)
+    }
+
+    /// The color of the pixel at (x, y). With multiple samples the shutter interval is split
+    /// into equal parts and one ray is cast at a random time in each of them. The path tracer
+    /// also spreads its rays over the area of the pixel, which smooths edges.
+    pub fn color_at_pixel(&self, world: &World, x: usize, y: usize) -> Color {
+        if self.samples == 1 && self.integrator == Integrator::Whitted {
+            return world.color_at(
+                &self.ray_for_pixel(x, y),
+                MAXIMUM_REFLECTION_RECURSION_DEPTH,
+            );
+        }
+        // seeding by pixel keeps renders reproducible regardless of the order of the pixels
+        let mut rng = Rng::from_seeds(&[x as u64, y as u64]);
+        let exposure = self.shutter_close - self.shutter_open;
+        let samples = self.samples as f64;
+        (0..self.samples)
+            .map(|i| {
+                let time = self.shutter_open + exposure * (i as f64 + rng.next_f64()) / samples;
+                match &self.integrator {
+                    Integrator::Whitted => world.color_at(
+                        &self.ray_for_pixel_at(x, y, time),
+                        MAXIMUM_REFLECTION_RECURSION_DEPTH,
+                    ),
+                    Integrator::PathTracer(tracer) => {
+                        let (dx, dy) = (rng.next_f64(), rng.next_f64());
+                        let ray = self.ray_through(x as f64 + dx, y as f64 + dy, time);
+                        tracer.radiance(world, &ray, &mut rng)
+                    }
+                }
+            })
+            .fold(Color::black(), |sum, color| sum + color)
+            * (1. / self.samples as f32)
     }
 
     pub fn render(self, world: World) -> Canvas {
@@ -118,17 +216,17 @@ impl Camera {
         let locked_world = Arc::new(RwLock::new(world));
         for chunk in chunks {
             let t_tx = tx.clone();
-            let chunk = chunk.iter().copied().collect::<Vec<usize>>();
+            let chunk = chunk.to_vec();
             let t_cam = Arc::clone(&locked_cam);
             let t_world = Arc::clone(&locked_world);
             spawn(move || {
                 for y in chunk {
                     for x in 0..width {
-                        let ray = t_cam.read().unwrap().ray_for_pixel(x, y);
-                        let color = t_world
-                            .read()
-                            .unwrap()
-                            .color_at(&ray, MAXIMUM_REFLECTION_RECURSION_DEPTH);
+                        let color =
+                            t_cam
+                                .read()
+                                .unwrap()
+                                .color_at_pixel(&t_world.read().unwrap(), x, y);
                         let _ = t_tx.send((x, y, color));
                     }
                 }
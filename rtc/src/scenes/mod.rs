@@ -1,7 +1,11 @@
+pub use background::*;
 pub use camera::*;
+pub use path_tracer::*;
 pub use world::*;
 
+mod background;
 mod camera;
+mod path_tracer;
 mod world;
 
 #[cfg(test)]
@@ -0,0 +1,226 @@
+use std::f64::consts::PI;
+
+use crate::{
+    primitives::{
+        ray::Ray,
+        vector::{vector, CrossProd, ScalarProd, Vec3D},
+    },
+    shading::{beer_lambert, cook_torrance, Color, ShadingModel},
+    shapes::PreComp,
+    utils::random::Rng,
+};
+
+use super::World;
+
+/// Monte Carlo path tracer. Every path starts at the camera and bounces through the scene. At
+/// each surface the light it emits and the direct light of all lights are added (next-event
+/// estimation), then one way to continue is picked at random: a diffuse bounce, a mirror
+/// reflection or a refraction. Averaged over many samples per pixel this converges to an image
+/// with global illumination, e.g. light bleeding from a red wall onto the floor next to it.
+/// Emissive shapes light the scene too, paths pick up their light when they hit them, as does
+/// the background of the world for paths that leave the scene.
+///
+/// Unlike the recursive tracer it ignores the `ambient` of materials, the light it stands in for
+/// is computed. The specular highlights of Phong materials only come from direct light.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathTracer {
+    /// Number of bounces after which Russian roulette may end a path. Paths that carry little
+    /// light are ended with a high probability, surviving paths are weighted up accordingly so
+    /// the image stays unbiased.
+    pub min_bounces: usize,
+    /// Paths are cut off after this many bounces. This darkens the image a tiny bit, but keeps
+    /// e.g. two facing mirrors from bouncing a path forever.
+    pub max_bounces: usize,
+}
+
+impl Default for PathTracer {
+    fn default() -> Self {
+        PathTracer {
+            min_bounces: 3,
+            max_bounces: 64,
+        }
+    }
+}
+
+/// The ways a path can continue at a surface
+enum Event {
+    /// Bounce into a random direction following the reflectance of the material
+    Diffuse,
+    Reflection,
+    Refraction(Vec3D),
+}
+
+impl PathTracer {
+    pub fn new(min_bounces: usize, max_bounces: usize) -> Self {
+        PathTracer {
+            min_bounces,
+            max_bounces,
+        }
+    }
+
+    /// Estimate the light arriving along `ray` by following one random path
+    pub fn radiance(&self, world: &World, ray: &Ray, rng: &mut Rng) -> Color {
+        let mut radiance = Color::black();
+        let mut throughput = Color::white();
+        let mut ray = ray.clone();
+        // absorption of the medium the path currently travels through
+        let mut absorption = Color::black();
+        for bounce in 0..self.max_bounces {
+            let is = world.intersect(&ray);
+            let hit = match is.hit() {
+                Some(hit) => hit,
+                None => {
+                    let background = world.background_at(&ray, bounce == 0);
+                    radiance = radiance
+                        + throughput * beer_lambert(absorption, f64::INFINITY) * background;
+                    break;
+                }
+            };
+            throughput = throughput * beer_lambert(absorption, hit.t);
+            let comps = hit.prepare_computations(&ray, &is);
+            let color = comps.surface_color();
+            radiance = radiance
+                + throughput * (comps.material().emission + direct_light(world, &comps, color));
+
+            if bounce >= self.min_bounces {
+                let survival = max_channel(throughput).clamp(0.05, 1.);
+                if rng.next_f64() as f32 >= survival {
+                    break;
+                }
+                throughput = throughput * (1. / survival);
+            }
+
+            let (event, weight) = match choose_event(&comps, color, rng) {
+                Some(choice) => choice,
+                None => break,
+            };
+            ray = match event {
+                Event::Diffuse => {
+                    let direction = cosine_weighted(&comps.normal, rng);
+                    throughput = throughput * diffuse_weight(&comps, color, &direction);
+                    Ray::new_with_time(comps.over_point.clone(), direction, comps.time)
+                }
+                Event::Reflection => Ray::new_with_time(
+                    comps.over_point.clone(),
+                    comps.reflection.clone(),
+                    comps.time,
+                ),
+                Event::Refraction(direction) => {
+                    absorption = comps.absorption2;
+                    Ray::new_with_time(comps.under_point.clone(), direction, comps.time)
+                }
+            };
+            throughput = throughput * weight;
+            if throughput == Color::black() {
+                break;
+            }
+        }
+        radiance
+    }
+}
+
+/// Light of all lights reflected at the hit towards the eye, with the intensities of lights
+/// following the convention of `Light` for every shading model. The light of emissive shapes is
+/// found by bouncing into them with the BRDF of the surface, sampling them as well would count
+/// it twice.
+fn direct_light(world: &World, comps: &PreComp, color: Color) -> Color {
+    world
+        .lights
+        .iter()
+        .filter(|light| !light.is_emissive_shape())
+        .map(|light| {
+            let samples = world.light_samples(light.as_ref(), &comps.over_point, comps.time);
+            light.direct_lighting(color, comps.material(), &comps.eye, &comps.normal, &samples)
+        })
+        .fold(Color::black(), |sum, color| sum + color)
+}
+
+/// Pick how the path continues, with a probability proportional to how much light each event
+/// carries. Returns the event together with the weight the throughput has to be multiplied with,
+/// `None` if no light leaves the surface.
+fn choose_event(comps: &PreComp, color: Color, rng: &mut Rng) -> Option<(Event, f32)> {
+    let material = comps.material();
+    let diffuse = match material.model {
+        ShadingModel::Phong => max_channel(color) * material.diffuse,
+        // the bounce samples the whole microfacet BRDF, its glossy reflection included
+        ShadingModel::CookTorrance { .. } => 1.,
+    };
+    let refraction = if material.transparency > 0. {
+        comps.refraction()
+    } else {
+        None
+    };
+    // like the recursive tracer, Fresnel splits the light between reflection and refraction
+    // when a material has both
+    let (reflected, refracted) = match refraction {
+        Some(_) if material.reflectiveness > 0. => {
+            let reflectance = comps.schlick() as f32;
+            (reflectance, 1. - reflectance)
+        }
+        Some(_) => (0., material.transparency),
+        None => (material.reflectiveness, 0.),
+    };
+    let total = diffuse + reflected + refracted;
+    if total <= 0. {
+        return None;
+    }
+    let choice = rng.next_f64() as f32 * total;
+    let event = if choice < diffuse {
+        Event::Diffuse
+    } else {
+        match refraction {
+            Some(direction) if choice >= diffuse + reflected => Event::Refraction(direction),
+            _ => Event::Reflection,
+        }
+    };
+    // the weight of the event divided by the probability of choosing it
+    Some((event, total))
+}
+
+/// Factor for the throughput of a diffuse bounce into `direction` drawn by `cosine_weighted`,
+/// relative to the share of diffuse light `choose_event` picked the bounce with
+fn diffuse_weight(comps: &PreComp, color: Color, direction: &Vec3D) -> Color {
+    let material = comps.material();
+    match material.model {
+        // the albedo `color * diffuse` divided by its share `max_channel(color) * diffuse`
+        ShadingModel::Phong => color * (1. / max_channel(color)),
+        ShadingModel::CookTorrance {
+            metallic,
+            roughness,
+        } => {
+            let cos = (&comps.normal).scalar_prod(direction);
+            if cos <= 0. {
+                return Color::black();
+            }
+            // the BRDF times the cosine divided by the density cos/π of the direction
+            cook_torrance(
+                color,
+                metallic,
+                roughness,
+                &comps.normal,
+                &comps.eye,
+                direction,
+            ) * (PI / cos) as f32
+        }
+    }
+}
+
+/// Random direction in the hemisphere around `normal`, directions close to the normal are more
+/// likely in proportion to the cosine of their angle to it
+fn cosine_weighted(normal: &Vec3D, rng: &mut Rng) -> Vec3D {
+    let helper = if normal.x().abs() < 0.9 {
+        vector(1., 0., 0.)
+    } else {
+        vector(0., 1., 0.)
+    };
+    let u = (&helper).cross(normal).unit();
+    let v = normal.cross(&u);
+    let r = rng.next_f64().sqrt();
+    let angle = 2. * PI * rng.next_f64();
+    let z = (1. - r * r).max(0.).sqrt();
+    &u * (r * angle.cos()) + &v * (r * angle.sin()) + normal * z
+}
+
+fn max_channel(color: Color) -> f32 {
+    color.r.max(color.g).max(color.b)
+}
@@ -1,14 +1,22 @@
+// materials are set up one field at a time, like in the book
+#![allow(clippy::field_reassign_with_default)]
+
 use super::*;
 
 use crate::{
     assert_approx_eq,
     primitives::{
         approx_eq::EPSILON_F64,
+        canvas::Canvas,
         ray::Ray,
         vector::{point, vector, Point, Transformation},
     },
-    shading::{Color, Material, Pattern, PointLight, TEST_PATTERN},
-    shapes::{Intersection, Intersections, Shape, SPHERE},
+    shading::{
+        Color, DirectionalLight, Filter, ImageTexture, LightSample, Material, Pattern, PointLight,
+        RectangleLight, UvMapping, Wrap, TEST_PATTERN,
+    },
+    shapes::{Intersection, Intersections, Motion, Shape, Sphere},
+    utils::random::Rng,
 };
 
 use std::{f64::consts, sync::Arc};
@@ -25,11 +33,33 @@ fn intersect() {
     assert_approx_eq!(xs[3].t, 6.0);
 }
 
+#[test]
+fn changing_objects_updates_bvh() {
+    let mut w = World::default();
+    let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
+    assert_eq!(w.intersect(&r).len(), 4);
+
+    let mut s = Shape::default();
+    s.set_transform(Transformation::new_translation(0., 0., 3.));
+    w.add_object(Arc::new(s));
+    assert_eq!(w.intersect(&r).len(), 6);
+
+    w.object_mut(2)
+        .set_transform(Transformation::new_translation(0., 5., 0.));
+    assert_eq!(w.intersect(&r).len(), 4);
+
+    w.remove_object(0);
+    w.remove_object(1);
+    let xs = w.intersect(&r);
+    assert_eq!(xs.len(), 2);
+    assert_approx_eq!(xs[0].t, 4.5);
+}
+
 #[test]
 fn shade_intersection() {
     let w = World::default();
     let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
-    let s = &w.objects[0];
+    let s = &w.objects()[0];
     let i = Intersection::new(4., Arc::clone(s));
     let is = Intersections::new(vec![i]);
     let comps = is[0].prepare_computations(&r, &is);
@@ -40,12 +70,12 @@ fn shade_intersection() {
 #[test]
 fn shade_intersection_inside() {
     let mut w = World::default();
-    w.lights = vec![PointLight::new(
+    w.lights = vec![Box::new(PointLight::new(
         point(0., 0.25, 0.),
         Color::new_rgb(1., 1., 1.),
-    )];
+    ))];
     let r = Ray::new(point(0., 0., 0.), vector(0., 0., 1.));
-    let s = &w.objects[1];
+    let s = &w.objects()[1];
     let i = Intersection::new(0.5, Arc::clone(s));
     let is = Intersections::new(vec![i]);
     let comps = is[0].prepare_computations(&r, &is);
@@ -53,6 +83,22 @@ fn shade_intersection_inside() {
     assert_approx_eq!(c, Color::new_rgb(0.90498, 0.90498, 0.90498));
 }
 
+#[test]
+fn shade_intersection_lights_add_up() {
+    let mut w = World::default();
+    w.add_light(PointLight::new(point(-10., 10., -10.), Color::white()));
+    let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
+    let i = Intersection::new(4., Arc::clone(&w.objects()[0]));
+    let is = Intersections::new(vec![i]);
+    let comps = is[0].prepare_computations(&r, &is);
+    let c = w.shade_hit(&comps, 1);
+    assert_approx_eq!(c, Color::new_rgb(0.76132, 0.95166, 0.571));
+
+    // without lights only reflected and refracted light remains
+    w.lights.clear();
+    assert_approx_eq!(w.shade_hit(&comps, 1), Color::black());
+}
+
 #[test]
 fn color_at_miss() {
     let w = World::default();
@@ -72,12 +118,9 @@ fn color_at_hit() {
 #[test]
 fn color_at_from_inside() {
     let mut w = World::default();
-    let outer = Arc::make_mut(&mut w.objects[0]);
-    outer.material.ambient = 1.0;
-    drop(outer);
-    let inner = Arc::make_mut(&mut w.objects[1]);
-    inner.material.ambient = 1.0;
-    let inner = &w.objects[1];
+    w.object_mut(0).material.ambient = 1.0;
+    w.object_mut(1).material.ambient = 1.0;
+    let inner = &w.objects()[1];
 
     let r = Ray::new(point(0., 0., 0.75), vector(0., 0., -1.));
     let c = w.color_at(&r, 1);
@@ -132,6 +175,218 @@ fn cons_ray_transformed_cam() {
     assert_approx_eq!(ray.direction, &vector(a, 0., -a));
 }
 
+#[test]
+fn cons_ray_during_exposure() {
+    let mut cam = Camera::new(201, 101, consts::FRAC_PI_2, Transformation::identity());
+    assert_approx_eq!(cam.ray_for_pixel(100, 50).time, 0.);
+    cam.set_shutter(0.5, 1.5);
+    assert_approx_eq!(cam.ray_for_pixel(100, 50).time, 0.5);
+    let ray = cam.ray_for_pixel_at(100, 50, 0.75);
+    assert_approx_eq!(ray.time, 0.75);
+    assert_approx_eq!(ray.direction, &vector(0., 0., -1.));
+}
+
+#[test]
+fn motion_blur() {
+    let glowing = Material::new(Color::white(), 1., 0., 0., 200., 0., 0., 1.);
+    let mut s = Shape::new_sphere(glowing, Transformation::identity());
+    // covers the center of the image for the first tenth of the exposure
+    s.set_motion(
+        Motion::linear(
+            Transformation::new_translation(0., 0., -5.),
+            Transformation::new_translation(10., 0., -5.),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    let light = PointLight::new(point(0., 0., 0.), Color::white());
+    let w = World::new(vec![s], vec![Box::new(light)]);
+    let mut cam = Camera::new(11, 11, consts::FRAC_PI_2, Transformation::identity());
+    assert_approx_eq!(cam.color_at_pixel(&w, 5, 5), Color::white());
+    cam.set_shutter(0., 1.);
+    cam.set_samples(10);
+    assert_approx_eq!(cam.color_at_pixel(&w, 5, 5), Color::white() * 0.1);
+}
+
+#[test]
+fn path_tracer_miss() {
+    let w = World::default();
+    let r = Ray::new(point(0., 0., -5.), vector(0., 1., 0.));
+    let c = PathTracer::default().radiance(&w, &r, &mut Rng::new(1));
+    assert_approx_eq!(c, Color::black());
+}
+
+#[test]
+fn path_tracer_direct_light() {
+    // light bouncing off a lone convex object never returns to it
+    let w = World::new(
+        vec![Shape::default()],
+        vec![Box::new(PointLight::new(
+            point(-10., 10., -10.),
+            Color::white(),
+        ))],
+    );
+    let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
+    let mut rng = Rng::new(1);
+    let ambient = Color::white() * Material::default().ambient;
+    for _ in 0..10 {
+        let c = PathTracer::default().radiance(&w, &r, &mut rng);
+        assert_approx_eq!(c, w.color_at(&r, 5) - ambient);
+    }
+}
+
+#[test]
+fn path_tracer_color_bleeding() {
+    let mut red = Material::default();
+    red.color = Color::new_rgb(1., 0., 0.);
+    red.specular = 0.;
+    let w = World::new(
+        vec![
+            Shape::new_plane(Material::default(), Transformation::identity()),
+            Shape::new_plane(
+                red,
+                Transformation::new_z_rotation(consts::FRAC_PI_2).translated(1., 0., 0.),
+            ),
+        ],
+        vec![Box::new(PointLight::new(
+            point(-5., 5., 0.),
+            Color::white(),
+        ))],
+    );
+    let r = Ray::new(point(0.5, 5., 0.), vector(0., -1., 0.));
+    let direct = w.color_at(&r, 5) - Color::white() * Material::default().ambient;
+    let mut rng = Rng::new(7);
+    let samples = 500;
+    let c = (0..samples)
+        .map(|_| PathTracer::default().radiance(&w, &r, &mut rng))
+        .fold(Color::black(), |sum, c| sum + c)
+        * (1. / samples as f32);
+    // the red wall lights the white floor next to it
+    assert!(c.r > direct.r * 1.1);
+    // but can't contribute any blue light
+    assert_approx_eq!(c.b, direct.b);
+}
+
+#[test]
+fn path_tracer_facing_mirrors() {
+    let mut mirror = Material::default();
+    mirror.diffuse = 0.;
+    mirror.specular = 0.;
+    mirror.reflectiveness = 1.;
+    let w = World::new(
+        vec![
+            Shape::new_plane(mirror.clone(), Transformation::new_translation(0., -1., 0.)),
+            Shape::new_plane(mirror, Transformation::new_translation(0., 1., 0.)),
+        ],
+        vec![Box::new(PointLight::default())],
+    );
+    let r = Ray::new(Point::origin(), vector(0., 1., 0.));
+    let c = PathTracer::default().radiance(&w, &r, &mut Rng::new(1));
+    assert_approx_eq!(c, Color::black());
+}
+
+#[test]
+fn camera_path_tracer() {
+    let mut cam = Camera::new(11, 11, consts::FRAC_PI_2, Transformation::identity());
+    assert_eq!(cam.integrator(), &Integrator::Whitted);
+    cam.set_integrator(Integrator::PathTracer(PathTracer::default()));
+    cam.set_samples(4);
+    let w = World::new(
+        vec![Shape::new_sphere(
+            Material::default(),
+            Transformation::new_translation(0., 0., -5.),
+        )],
+        vec![Box::new(PointLight::new(point(0., 0., 0.), Color::white()))],
+    );
+    // seeded by pixel, renders are reproducible
+    let c = cam.color_at_pixel(&w, 5, 5);
+    assert_approx_eq!(c, cam.color_at_pixel(&w, 5, 5));
+    assert!(c.r > 0.5);
+    assert_approx_eq!(cam.color_at_pixel(&w, 0, 0), Color::black());
+}
+
+/// A floor below an emissive rectangle, there are no other lights
+fn lamp_world() -> (World, Shape) {
+    let lamp = Shape::new_rectangle(
+        2.,
+        2.,
+        Material::new_emissive(Color::new_rgb(1., 0.8, 0.6), 5.),
+        Transformation::new_translation(0., 3., 0.),
+    );
+    let mut floor = Material::default();
+    floor.specular = 0.;
+    let w = World::new(
+        vec![Shape::new_plane(floor, Transformation::identity())],
+        vec![],
+    );
+    (w, lamp)
+}
+
+#[test]
+fn color_at_emissive() {
+    let (mut w, lamp) = lamp_world();
+    w.add_object(Arc::new(lamp));
+    let r = Ray::new(point(0., 5., 0.), vector(0., -1., 0.));
+    assert_approx_eq!(w.color_at(&r, 5), Color::new_rgb(5., 4., 3.));
+    // without a light the recursive tracer leaves the floor dark
+    let r = Ray::new(point(3., 5., 0.), vector(0., -1., 0.));
+    assert_approx_eq!(w.color_at(&r, 5), Color::black());
+}
+
+#[test]
+fn mesh_light_lights_scene() {
+    let (mut w, lamp) = lamp_world();
+    w.add_mesh_light(lamp, 16).unwrap();
+    assert_eq!(w.objects().len(), 2);
+    let p = point(0.5, EPSILON_F64, 0.5);
+    // the lamp doesn't shadow itself
+    assert_approx_eq!(w.intensity_at(w.lights[0].as_ref(), &p, 0.), Color::white());
+    let r = Ray::new(point(1., 1., 0.), vector(0., -1., 0.));
+    let c = w.color_at(&r, 5);
+    assert!(c.r > 0.5 && c.r > c.g && c.g > c.b);
+}
+
+#[test]
+fn mesh_light_unsupported_shape() {
+    let (mut w, _) = lamp_world();
+    let lamp = Shape::new_sphere(
+        Material::new_emissive(Color::white(), 5.),
+        Transformation::new_translation(0., 3., 0.),
+    );
+    assert!(w.add_mesh_light(lamp, 16).is_err());
+    assert_eq!(w.objects().len(), 1);
+    assert!(w.lights.is_empty());
+}
+
+#[test]
+fn path_tracer_emissive() {
+    let (w, lamp) = lamp_world();
+    let mut rng = Rng::new(3);
+    let mut with_lamp = World::new(vec![], vec![]);
+    with_lamp.add_object(Arc::new(lamp.clone()));
+    let r = Ray::new(point(0., 5., 0.), vector(0., -1., 0.));
+    let c = PathTracer::default().radiance(&with_lamp, &r, &mut rng);
+    assert_approx_eq!(c, Color::new_rgb(5., 4., 3.));
+
+    // the floor is lit by bouncing into the lamp, sampling it as mesh light adds nothing
+    let mut w = w;
+    w.add_mesh_light(lamp, 64).unwrap();
+    let r = Ray::new(point(1., 1., 0.), vector(0., -1., 0.));
+    let samples = 2000;
+    let c = (0..samples)
+        .map(|_| PathTracer::default().radiance(&w, &r, &mut rng))
+        .fold(Color::black(), |sum, c| sum + c)
+        * (1. / samples as f32);
+    // diffuse reflectance times red emission times the form factor of the lamp seen from the
+    // floor, which is the integral of cos² / (π d²) over the lamp
+    let expected = 0.9 * 5. * 0.10454;
+    assert!((c.r - expected).abs() < expected * 0.1);
+    // the recursive tracer samples the lamp as mesh light and gets the same result
+    let ambient = w.lights[0].ambient(&point(1., 0., 0.)) * Material::default().ambient;
+    let direct = w.color_at(&r, 5) - ambient;
+    assert!((direct.r - expected).abs() < expected * 0.05);
+}
+
 #[test]
 fn render_default_with_cam() {
     let from = point(0., 0., -5.);
@@ -147,47 +402,178 @@ fn render_default_with_cam() {
     assert_approx_eq!(image[(5, 5)], Color::new_rgb(0.38066, 0.47583, 0.2855));
 }
 
+#[test]
+fn intensity_at_point_light() {
+    let w = World::default();
+    let light = w.lights[0].as_ref();
+    for (p, expected) in [
+        (point(0., 1.0001, 0.), 1.),
+        (point(-1.0001, 0., 0.), 1.),
+        (point(0., 0., -1.0001), 1.),
+        (point(0., 0., 1.0001), 0.),
+        (point(1.0001, 0., 0.), 0.),
+        (point(0., -1.0001, 0.), 0.),
+        (point(0., 0., 0.), 0.),
+    ] {
+        assert_approx_eq!(w.intensity_at(light, &p, 0.), Color::white() * expected);
+    }
+}
+
+#[test]
+fn intensity_at_area_light() {
+    let w = World::default();
+    let mut light = RectangleLight::new(
+        point(-0.5, -0.5, -5.),
+        vector(1., 0., 0.),
+        2,
+        vector(0., 1., 0.),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    for (p, expected) in [
+        (point(0., 0., 2.), 0.),
+        (point(1., -1., 2.), 0.25),
+        (point(1.5, 0., 2.), 0.5),
+        (point(1.25, 1.25, 3.), 0.75),
+        (point(0., 0., -2.), 1.),
+    ] {
+        assert_approx_eq!(w.intensity_at(&light, &p, 0.), Color::white() * expected);
+    }
+}
+
+/// Sample of the light in the default world as seen from `p`
+fn light_sample(w: &World, p: &Point) -> LightSample {
+    w.lights[0].samples(p).remove(0)
+}
+
 #[test]
 fn shadow_nothing_colinear() {
     let w = World::default();
     let p = point(0., 10., 0.);
-    assert!(!w.is_shadowed(&p)[0]);
+    assert!(!w.is_shadowed(&p, &light_sample(&w, &p), 0.));
 }
 
 #[test]
 fn shadow_object_in_path() {
     let w = World::default();
     let p = point(10., -10., 10.);
-    assert!(w.is_shadowed(&p)[0]);
+    assert!(w.is_shadowed(&p, &light_sample(&w, &p), 0.));
 }
 
 #[test]
 fn shadow_point_behind() {
     let w = World::default();
     let p = point(-20., 20., -20.);
-    assert!(!w.is_shadowed(&p)[0]);
+    assert!(!w.is_shadowed(&p, &light_sample(&w, &p), 0.));
 }
 
 #[test]
 fn shadow_object_behind() {
     let w = World::default();
     let p = point(-2., 2., -2.);
-    assert!(!w.is_shadowed(&p)[0]);
+    assert!(!w.is_shadowed(&p, &light_sample(&w, &p), 0.));
+}
+
+#[test]
+fn shadow_directional_light() {
+    let blocker = Shape::new(
+        Sphere,
+        Material::default(),
+        Transformation::new_translation(0., 1000., 0.),
+    );
+    let w = World::new(
+        vec![blocker],
+        vec![Box::new(DirectionalLight::new(
+            vector(0., -1., 0.),
+            Color::white(),
+        ))],
+    );
+    // directional lights are infinitely far away, so distant objects still cast shadows
+    let p = Point::origin();
+    assert!(w.is_shadowed(&p, &light_sample(&w, &p), 0.));
+    let p = point(5., 0., 0.);
+    assert!(!w.is_shadowed(&p, &light_sample(&w, &p), 0.));
+}
+
+#[test]
+fn shadow_transparent_objects() {
+    let mut glass = Material::default();
+    glass.transparency = 0.5;
+    glass.tint = Color::new_rgb(1., 0.5, 0.);
+    let w = World::new(
+        vec![
+            Shape::new_sphere(glass.clone(), Transformation::new_translation(0., 5., 0.)),
+            Shape::new_plane(glass, Transformation::new_translation(0., 2., 0.)),
+        ],
+        vec![Box::new(PointLight::new(
+            point(0., 10., 0.),
+            Color::white(),
+        ))],
+    );
+    // the light crosses two surfaces of the sphere and the plane, but is tinted once by each
+    let p = Point::origin();
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.125, 0.03125, 0.)
+    );
+    // from inside of the sphere the light leaves it through one surface
+    let p = point(0., 5., 0.);
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.5, 0.25, 0.)
+    );
+    assert!(!w.is_shadowed(&p, &light_sample(&w, &p), 0.));
+    // only the plane is in the way
+    let p = point(5., 0., 0.);
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.5, 0.25, 0.)
+    );
+    assert_approx_eq!(
+        w.intensity_at(w.lights[0].as_ref(), &p, 0.),
+        Color::new_rgb(0.5, 0.25, 0.)
+    );
+}
+
+#[test]
+fn shadow_opaque_behind_transparent() {
+    let mut glass = Material::default();
+    glass.transparency = 0.9;
+    let w = World::new(
+        vec![
+            Shape::new_plane(glass, Transformation::new_translation(0., 2., 0.)),
+            Shape::new_plane(
+                Material::default(),
+                Transformation::new_translation(0., 4., 0.),
+            ),
+        ],
+        vec![Box::new(PointLight::new(
+            point(0., 10., 0.),
+            Color::white(),
+        ))],
+    );
+    let p = Point::origin();
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::black()
+    );
+    assert!(w.is_shadowed(&p, &light_sample(&w, &p), 0.));
 }
 
 #[test]
 fn shade_hit_intersection_in_shadow() {
     let s2 = Shape::new(
-        SPHERE,
+        Sphere,
         Material::default(),
         Transformation::new_translation(0., 0., 10.),
     );
     let w = World::new(
         vec![Shape::default(), s2.clone()],
-        vec![PointLight::new(
+        vec![Box::new(PointLight::new(
             point(0., 0., -10.),
             Color::new_rgb(1., 1., 1.),
-        )],
+        ))],
     );
     let r = Ray::new(point(0., 0., 5.), vector(0., 0., 1.));
     let i = Intersection::new(4., Arc::new(s2));
@@ -201,7 +587,7 @@ fn shade_hit_intersection_in_shadow() {
 fn shadow_hit_offset_over_point() {
     let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
     let shape = Shape::new(
-        SPHERE,
+        Sphere,
         Material::default(),
         Transformation::new_translation(0., 0., 1.),
     );
@@ -217,7 +603,7 @@ fn shadow_hit_offset_over_point() {
 fn under_point() {
     let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
     let shape = Shape::new(
-        SPHERE,
+        Sphere,
         Material::default(),
         Transformation::new_translation(0., 0., 1.),
     );
@@ -233,7 +619,7 @@ fn under_point() {
 fn nonreflective_reflection() {
     let light = PointLight::new(point(-10., 10., -10.), Color::new_rgb(1., 1., 1.));
     let s1 = Shape::new(
-        SPHERE,
+        Sphere,
         Material::new(
             Color::new_rgb(0.8, 1.0, 0.6),
             0.1,
@@ -249,9 +635,9 @@ fn nonreflective_reflection() {
     let mut s2 = Shape::default();
     s2.modify_transform(|t| t.scale(0.5, 0.5, 0.5));
     s2.material.ambient = 1.0;
-    let w = World::new(vec![s1, s2], vec![light]);
+    let w = World::new(vec![s1, s2], vec![Box::new(light)]);
     let r = Ray::new(Point::origin(), vector(0., 0., 1.));
-    let shape = &w.objects[1];
+    let shape = &w.objects()[1];
     let i = Intersection::new(1., Arc::clone(shape));
     let is = Intersections::new(vec![i]);
     let comps = is[0].prepare_computations(&r, &is);
@@ -259,6 +645,180 @@ fn nonreflective_reflection() {
     assert_approx_eq!(color, Color::black());
 }
 
+#[test]
+fn background_color_and_gradient() {
+    let sky = Background::Color(Color::new_rgb(0.2, 0.4, 0.6));
+    assert_approx_eq!(
+        sky.color_at(&vector(1., 2., 3.)),
+        Color::new_rgb(0.2, 0.4, 0.6)
+    );
+    assert_approx_eq!(
+        Background::default().color_at(&vector(0., 1., 0.)),
+        Color::black()
+    );
+
+    let gradient = Background::Gradient {
+        bottom: Color::white(),
+        top: Color::new_rgb(0.2, 0.4, 1.),
+    };
+    assert_approx_eq!(
+        gradient.color_at(&vector(0., 2., 0.)),
+        Color::new_rgb(0.2, 0.4, 1.)
+    );
+    assert_approx_eq!(gradient.color_at(&vector(0., -1., 0.)), Color::white());
+    assert_approx_eq!(
+        gradient.color_at(&vector(1., 0., 0.)),
+        Color::new_rgb(0.6, 0.7, 1.)
+    );
+}
+
+/// A background texture showing the closest pixel, so single pixels can be checked
+fn nearest_texture(image: Canvas, mapping: UvMapping) -> ImageTexture {
+    let mut texture = ImageTexture::new(image, mapping);
+    texture.filter = Filter::Nearest;
+    texture.wrap = Wrap::Clamp;
+    texture
+}
+
+#[test]
+fn background_equirectangular() {
+    let mut image = Canvas::new(4, 2);
+    for j in 0..4 {
+        image[(0, j)] = Color::new_rgb(j as f32, 0., 0.);
+        image[(1, j)] = Color::new_rgb(j as f32, 1., 0.);
+    }
+    let panorama = Background::Equirectangular(nearest_texture(image, UvMapping::Spherical));
+    let cases = vec![
+        (vector(0., -0.1, -1.), Color::new_rgb(2., 1., 0.)),
+        (vector(1., -0.1, 0.), Color::new_rgb(1., 1., 0.)),
+        (vector(-1., -0.1, 0.), Color::new_rgb(3., 1., 0.)),
+        (vector(0., 1., -0.01), Color::new_rgb(2., 0., 0.)),
+        (vector(0.1, 0.2, 1.), Color::new_rgb(0., 0., 0.)),
+    ];
+    for (direction, color) in cases {
+        assert_approx_eq!(panorama.color_at(&direction), color);
+    }
+}
+
+#[test]
+fn background_cube_map() {
+    let faces = [0, 1, 2, 3, 4, 5].map(|face| {
+        let mut image = Canvas::new(2, 2);
+        for i in 0..2 {
+            for j in 0..2 {
+                image[(i, j)] = Color::new_rgb(face as f32, i as f32, j as f32);
+            }
+        }
+        nearest_texture(image, UvMapping::Cubic)
+    });
+    let cube = Background::CubeMap(Arc::new(faces));
+    let cases = vec![
+        // left, front, right and back, each seen at the top left of the face
+        (vector(-1., 0.5, -0.5), Color::new_rgb(0., 0., 0.)),
+        (vector(-0.5, 0.5, 1.), Color::new_rgb(1., 0., 0.)),
+        (vector(1., 0.5, 0.5), Color::new_rgb(2., 0., 0.)),
+        (vector(0.5, 0.5, -1.), Color::new_rgb(3., 0., 0.)),
+        // the top of the up face is at the back, that of the down face at the front
+        (vector(0.5, 1., 0.5), Color::new_rgb(4., 1., 1.)),
+        (vector(0.5, -1., 0.5), Color::new_rgb(5., 0., 1.)),
+        (vector(-0.5, -1., -0.5), Color::new_rgb(5., 1., 0.)),
+    ];
+    for (direction, color) in cases {
+        assert_approx_eq!(cube.color_at(&direction), color);
+    }
+}
+
+#[test]
+fn background_images() {
+    assert_eq!(
+        Background::equirectangular(Canvas::new(0, 0)).err(),
+        Some(BackgroundError::EmptyImage)
+    );
+    let faces = [1, 1, 1, 0, 1, 1].map(|size| Canvas::new(size, size));
+    assert_eq!(
+        Background::cube_map(faces).err(),
+        Some(BackgroundError::EmptyImage)
+    );
+    // the image is filtered like textures, halfway between two pixels they are blended
+    let mut image = Canvas::new(2, 1);
+    image[(0, 1)] = Color::white();
+    let panorama = Background::equirectangular(image).unwrap();
+    assert_approx_eq!(
+        panorama.color_at(&vector(0., 0., -1.)),
+        Color::white() * 0.5
+    );
+    // the edge pixels continue at the poles instead of wrapping around
+    let mut image = Canvas::new(1, 2);
+    image[(1, 0)] = Color::white();
+    let panorama = Background::equirectangular(image).unwrap();
+    assert_approx_eq!(panorama.color_at(&vector(0., -1., 0.)), Color::white());
+    assert_approx_eq!(panorama.color_at(&vector(0., 1., 0.)), Color::black());
+    let cube = Background::cube_map([(); 6].map(|_| Canvas::new(1, 1))).unwrap();
+    assert_approx_eq!(cube.color_at(&vector(1., 1., 1.)), Color::black());
+}
+
+/// A mirror facing upwards under a blue sky
+fn mirror_under_sky() -> World {
+    let mut mirror = Material::default();
+    mirror.reflectiveness = 0.5;
+    let mut w = World::new(
+        vec![Shape::new_plane(mirror, Transformation::identity())],
+        vec![],
+    );
+    w.background = Background::Color(Color::new_rgb(0.2, 0.4, 0.8));
+    w
+}
+
+#[test]
+fn world_background() {
+    let mut w = mirror_under_sky();
+    let up = Ray::new(point(0., 1., 0.), vector(0., 1., 1.));
+    let down = Ray::new(point(0., 1., 0.), vector(0., -1., 1.));
+    assert_approx_eq!(w.color_at(&up, 5), Color::new_rgb(0.2, 0.4, 0.8));
+    assert_approx_eq!(w.color_at(&down, 5), Color::new_rgb(0.1, 0.2, 0.4));
+
+    // without lighting the sky is only seen directly
+    w.background_lighting = false;
+    assert_approx_eq!(w.color_at(&up, 5), Color::new_rgb(0.2, 0.4, 0.8));
+    assert_approx_eq!(w.color_at(&down, 5), Color::black());
+}
+
+#[test]
+fn path_tracer_background() {
+    let mut w = World::new(
+        vec![Shape::new_plane(
+            Material::default(),
+            Transformation::identity(),
+        )],
+        vec![],
+    );
+    w.background = Background::Color(Color::white());
+    let mut rng = Rng::new(5);
+    let sky = Ray::new(point(0., 1., 0.), vector(0., 1., 0.));
+    assert_approx_eq!(
+        PathTracer::default().radiance(&w, &sky, &mut rng),
+        Color::white()
+    );
+    // every bounce off the floor reaches the sky
+    let floor = Ray::new(point(0., 1., 0.), vector(0., -1., 0.));
+    for _ in 0..10 {
+        assert_approx_eq!(
+            PathTracer::default().radiance(&w, &floor, &mut rng),
+            Color::white() * 0.9
+        );
+    }
+
+    w.background_lighting = false;
+    assert_approx_eq!(
+        PathTracer::default().radiance(&w, &sky, &mut rng),
+        Color::white()
+    );
+    assert_approx_eq!(
+        PathTracer::default().radiance(&w, &floor, &mut rng),
+        Color::black()
+    );
+}
+
 #[test]
 fn reflective_reflection() {
     let mut w = World::default();
@@ -268,7 +828,7 @@ fn reflective_reflection() {
         mat,
         Transformation::new_translation(0., -1., 0.),
     ));
-    w.objects.push(Arc::clone(&shape));
+    w.add_object(Arc::clone(&shape));
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., -3.), vector(0., -a, a));
     let i = Intersection::new(consts::SQRT_2, Arc::clone(&shape));
@@ -288,7 +848,7 @@ fn shade_hit_with_reflective_mat() {
         mat,
         Transformation::new_translation(0., -1., 0.),
     ));
-    w.objects.push(Arc::clone(&shape));
+    w.add_object(Arc::clone(&shape));
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., -3.), vector(0., -a, a));
     let i = Intersection::new(consts::SQRT_2, Arc::clone(&shape));
@@ -307,7 +867,7 @@ fn prevent_infinite_reflection() {
             Shape::new_plane(mat.clone(), Transformation::new_translation(0., -1., 0.)),
             Shape::new_plane(mat.clone(), Transformation::new_translation(0., 1., 0.)),
         ],
-        vec![PointLight::new(Point::origin(), Color::white())],
+        vec![Box::new(PointLight::new(Point::origin(), Color::white()))],
     );
     let r = Ray::new(point(0., 0., 0.), vector(0., 1., 0.));
     world.color_at(&r, 100);
@@ -322,7 +882,7 @@ fn reflection_at_maximum_recursion() {
         mat,
         Transformation::new_translation(0., -1., 0.),
     ));
-    w.objects.push(Arc::clone(&shape));
+    w.add_object(Arc::clone(&shape));
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., -3.), vector(0., -a, a));
     let i = Intersection::new(consts::SQRT_2, Arc::clone(&shape));
@@ -335,7 +895,7 @@ fn reflection_at_maximum_recursion() {
 #[test]
 fn refactored_opaque() {
     let w = World::default();
-    let shape = &w.objects[0];
+    let shape = &w.objects()[0];
     let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
     let xs = Intersections::new(vec![
         Intersection::new(4., Arc::clone(shape)),
@@ -351,7 +911,7 @@ fn refactored_at_max_depth() {
     let w = {
         let light = PointLight::new(point(-10., 10., -10.), Color::new_rgb(1., 1., 1.));
         let s1 = Shape::new(
-            SPHERE,
+            Sphere,
             Material::new(
                 Color::new_rgb(0.8, 1.0, 0.6),
                 0.1,
@@ -366,9 +926,9 @@ fn refactored_at_max_depth() {
         );
         let mut s2 = Shape::default();
         s2.modify_transform(|t| t.scale(0.5, 0.5, 0.5));
-        World::new(vec![s1, s2], vec![light])
+        World::new(vec![s1, s2], vec![Box::new(light)])
     };
-    let shape = &w.objects[0];
+    let shape = &w.objects()[0];
     let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
     let xs = Intersections::new(vec![
         Intersection::new(4., Arc::clone(shape)),
@@ -384,7 +944,7 @@ fn total_internal_reflection() {
     let w = {
         let light = PointLight::new(point(-10., 10., -10.), Color::new_rgb(1., 1., 1.));
         let s1 = Shape::new(
-            SPHERE,
+            Sphere,
             Material::new(
                 Color::new_rgb(1.0, 1.0, 1.0),
                 0.1,
@@ -399,9 +959,9 @@ fn total_internal_reflection() {
         );
         let mut s2 = Shape::default();
         s2.modify_transform(|t| t.scale(0.5, 0.5, 0.5));
-        World::new(vec![s1, s2], vec![light])
+        World::new(vec![s1, s2], vec![Box::new(light)])
     };
-    let shape = &w.objects[0];
+    let shape = &w.objects()[0];
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., a), vector(0., 1., 0.));
     let xs = Intersections::new(vec![
@@ -418,7 +978,7 @@ fn refracted_color() {
     let w = {
         let light = PointLight::new(point(-10., 10., -10.), Color::new_rgb(1., 1., 1.));
         let s1 = Shape::new(
-            SPHERE,
+            Sphere,
             Material::new_with_pattern(
                 Color::new_rgb(1.0, 1.0, 1.0),
                 Some(Pattern::new(TEST_PATTERN, Transformation::identity())),
@@ -437,11 +997,11 @@ fn refracted_color() {
         m2.refractive_index = 1.5;
         let mut s2 = Shape::new_sphere(m2, Transformation::identity());
         s2.modify_transform(|t| t.scale(0.5, 0.5, 0.5));
-        World::new(vec![s1, s2], vec![light])
+        World::new(vec![s1, s2], vec![Box::new(light)])
     };
     let r = Ray::new(point(0., 0., 0.1), vector(0., 1., 0.));
-    let shape_a = &w.objects[0];
-    let shape_b = &w.objects[1];
+    let shape_a = &w.objects()[0];
+    let shape_b = &w.objects()[1];
     let xs = Intersections::new(vec![
         Intersection::new(-0.9899, Arc::clone(shape_a)),
         Intersection::new(-0.4899, Arc::clone(shape_b)),
@@ -453,6 +1013,94 @@ fn refracted_color() {
     assert_approx_eq!(c, Color::new_rgb(0., 0.99888, 0.04725));
 }
 
+/// A unit sphere of clear glass that absorbs red light and glows white on its own
+fn absorbing_glass_world() -> World {
+    let mut glass = Material::default();
+    glass.ambient = 1.;
+    glass.diffuse = 0.;
+    glass.specular = 0.;
+    glass.transparency = 1.;
+    glass.absorption = Color::new_rgb(0.5, 0., 0.);
+    World::new(
+        vec![Shape::new_sphere(glass, Transformation::identity())],
+        vec![Box::new(PointLight::new(
+            point(0., 0., -10.),
+            Color::white(),
+        ))],
+    )
+}
+
+#[test]
+fn refracted_color_absorption() {
+    let w = absorbing_glass_world();
+    let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
+    let xs = w.intersect(&r);
+    let comps = xs[0].prepare_computations(&r, &xs);
+    assert_approx_eq!(comps.absorption2, Color::new_rgb(0.5, 0., 0.));
+    // the refracted ray travels 2 units through the glass to the glowing back side
+    let c = w.refracted_color(&comps, 5);
+    assert_approx_eq!(c, Color::new_rgb(0.36788, 1., 1.));
+
+    // a thinner slice of glass absorbs less
+    let r = Ray::new(point(0., 0.8, -5.), vector(0., 0., 1.));
+    let xs = w.intersect(&r);
+    let comps = xs[0].prepare_computations(&r, &xs);
+    let c = w.refracted_color(&comps, 5);
+    assert_approx_eq!(c, Color::new_rgb(0.54881, 1., 1.));
+}
+
+#[test]
+fn reflected_color_absorption() {
+    let mut w = absorbing_glass_world();
+    w.object_mut(0).material.reflectiveness = 1.;
+    // reflected back inside of the glass, the ray travels 2 units to the glowing front side
+    let r = Ray::new(Point::origin(), vector(0., 0., 1.));
+    let xs = w.intersect(&r);
+    let comps = xs[1].prepare_computations(&r, &xs);
+    assert!(comps.inside);
+    assert_approx_eq!(comps.absorption1, Color::new_rgb(0.5, 0., 0.));
+    let c = w.reflected_color(&comps, 1);
+    assert_approx_eq!(c, Color::new_rgb(0.36788, 1., 1.));
+}
+
+#[test]
+fn shadow_absorption() {
+    let w = absorbing_glass_world();
+    let p = point(0., 0., 5.);
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.36788, 1., 1.)
+    );
+    // starting inside of the glass the light is absorbed over the distance of 1 to its surface
+    let p = Point::origin();
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.60653, 1., 1.)
+    );
+}
+
+#[test]
+fn shadow_absorption_through_several_media() {
+    let mut w = absorbing_glass_world();
+    let glass = w.objects()[0].material.clone();
+    w.add_object(Arc::new(Shape::new_sphere(
+        glass,
+        Transformation::new_translation(0., 0., -4.),
+    )));
+    // behind the point, it neither shadows nor absorbs
+    w.add_object(Arc::new(Shape::new_sphere(
+        Material::default(),
+        Transformation::new_translation(0., 0., 5.),
+    )));
+    // absorbed over the distance of 1 to the surface of the glass containing the point and over
+    // the diameter of the other glass sphere
+    let p = Point::origin();
+    assert_approx_eq!(
+        w.transmittance(&p, &light_sample(&w, &p), 0.),
+        Color::new_rgb(0.22313, 1., 1.)
+    );
+}
+
 #[test]
 fn shade_hit_transparent() {
     let mut w = World::default();
@@ -460,11 +1108,13 @@ fn shade_hit_transparent() {
     let mut m = Material::default();
     m.transparency = 0.5;
     m.refractive_index = 1.5;
+    // the book's floor casts opaque shadows on the ball below
+    m.tint = Color::black();
     let floor = Arc::new(Shape::new_plane(
         m,
         Transformation::new_translation(0., -1., 0.),
     ));
-    w.objects.push(Arc::clone(&floor));
+    w.add_object(Arc::clone(&floor));
 
     let mut m2 = Material::default();
     m2.color = Color::new_rgb(1., 0., 0.);
@@ -473,7 +1123,7 @@ fn shade_hit_transparent() {
         m2,
         Transformation::new_translation(0., -3.5, -0.5),
     ));
-    w.objects.push(Arc::clone(&ball));
+    w.add_object(Arc::clone(&ball));
 
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., -3.), vector(0., -a, a));
@@ -491,11 +1141,12 @@ fn shade_hit_reflectance() {
     m.transparency = 0.5;
     m.refractive_index = 1.5;
     m.reflectiveness = 0.5;
+    m.tint = Color::black();
     let floor = Arc::new(Shape::new_plane(
         m,
         Transformation::new_translation(0., -1., 0.),
     ));
-    w.objects.push(Arc::clone(&floor));
+    w.add_object(Arc::clone(&floor));
 
     let mut m2 = Material::default();
     m2.color = Color::new_rgb(1., 0., 0.);
@@ -504,7 +1155,7 @@ fn shade_hit_reflectance() {
         m2,
         Transformation::new_translation(0., -3.5, -0.5),
     ));
-    w.objects.push(Arc::clone(&ball));
+    w.add_object(Arc::clone(&ball));
 
     let a = consts::SQRT_2 / 2.0;
     let r = Ray::new(point(0., 0., -3.), vector(0., -a, a));
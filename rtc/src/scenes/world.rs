@@ -1,24 +1,82 @@
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use crate::{
     primitives::{
-        approx_eq::ApproxEq,
+        approx_eq::{ApproxEq, EPSILON_F64},
         ray::Ray,
-        vector::{point, Point, ScalarProd, Transformation},
+        vector::{point, Point, Transformation},
     },
-    shading::{Color, Material, PointLight},
-    shapes::{Intersections, PreComp, Shape, SPHERE},
+    shading::{
+        beer_lambert, Color, Light, LightSample, Material, MeshLight, MeshLightError, PointLight,
+    },
+    shapes::{Bvh, Intersection, Intersections, PreComp, Shape, Sphere},
 };
 
+use super::Background;
+
 pub struct World {
-    pub objects: Vec<Arc<Shape>>,
-    pub lights: Vec<PointLight>,
+    /// Objects in the scene. They are only changed through methods that discard the bounding
+    /// volume hierarchy, so it never indexes a stale list.
+    objects: Vec<Arc<Shape>>,
+    pub lights: Vec<Box<dyn Light>>,
+    /// What rays that miss all objects see
+    pub background: Background,
+    /// Whether the background also shows in reflections and refractions and, in the path
+    /// tracer, lights the scene like a sky. Otherwise it is only seen directly by the camera.
+    pub background_lighting: bool,
+    /// Built by the first intersection after the objects changed
+    bvh: OnceLock<Bvh>,
 }
 
 impl World {
-    pub fn new(objects: Vec<Shape>, lights: Vec<PointLight>) -> Self {
-        let objects = objects.into_iter().map(Arc::new).collect::<Vec<_>>();
-        World { objects, lights }
+    pub fn new(objects: Vec<Shape>, lights: Vec<Box<dyn Light>>) -> Self {
+        World {
+            objects: objects.into_iter().map(Arc::new).collect(),
+            lights,
+            background: Background::default(),
+            background_lighting: true,
+            bvh: OnceLock::new(),
+        }
+    }
+
+    pub fn objects(&self) -> &[Arc<Shape>] {
+        &self.objects
+    }
+
+    /// Add an object to the scene. The bounding volume hierarchy is rebuilt by the next
+    /// intersection, so adding many objects in a row stays cheap.
+    pub fn add_object(&mut self, object: Arc<Shape>) {
+        self.objects.push(object);
+        self.bvh.take();
+    }
+
+    /// Remove the object at `index` from the scene and return it
+    pub fn remove_object(&mut self, index: usize) -> Arc<Shape> {
+        self.bvh.take();
+        self.objects.remove(index)
+    }
+
+    /// The object at `index` for changing e.g. its transformation or material. Objects shared
+    /// with other owners are cloned first.
+    pub fn object_mut(&mut self, index: usize) -> &mut Shape {
+        self.bvh.take();
+        Arc::make_mut(&mut self.objects[index])
+    }
+
+    pub fn add_light(&mut self, light: impl Light + 'static) {
+        self.lights.push(Box::new(light));
+    }
+
+    /// Add an emissive shape to the scene that also lights it, using `samples` points on its
+    /// surface. See `MeshLight` for the supported shapes, the world is left unchanged for others.
+    pub fn add_mesh_light(&mut self, shape: Shape, samples: usize) -> Result<(), MeshLightError> {
+        self.add_light(MeshLight::new(&shape, samples)?);
+        self.add_object(Arc::new(shape));
+        Ok(())
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::new(&self.objects))
     }
 
     pub fn new_empty() -> Self {
@@ -27,107 +85,197 @@ impl World {
 
     /// Find the intersections of a ray with all objects in the scene sorted by their t-value
     pub fn intersect(&self, ray: &Ray) -> Intersections {
-        let mut v = self
-            .objects
-            .iter()
-            .map(|shape| (shape.intersect)(Arc::clone(shape), ray))
-            .filter_map(|x| x.map(Intersections::into_iter))
-            .flatten()
-            .collect::<Vec<_>>();
-        v.sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
-        Intersections::new(v)
+        Intersections::new(
+            self.bvh()
+                .intersect(&self.objects, ray, -f64::INFINITY, f64::INFINITY),
+        )
     }
 
-    /// Cast a shadow ray to determine whether a point is in shadow
-    pub fn is_shadowed(&self, point: &Point) -> Vec<bool> {
-        self.lights
+    /// Cast a shadow ray at some point in time to determine the fraction of each color channel of
+    /// a sample of a light that reaches `point`. Every surface crossed on the way lets through
+    /// its transparency, like refracted rays do. The tint of a material filters the light once
+    /// per medium the ray passes through, not at both of its surfaces. Media absorb light along
+    /// the part of the ray inside of them, including the part from `point` if it lies inside of
+    /// one.
+    pub fn transmittance(&self, point: &Point, sample: &LightSample, time: f64) -> Color {
+        let ray = Ray::new_with_time((*point).clone(), sample.direction.clone(), time);
+        let mut ahead = self
+            .bvh()
+            .intersect(&self.objects, &ray, EPSILON_F64, sample.distance);
+        ahead.retain(|i| i.t > EPSILON_F64 && i.t < sample.distance);
+        if ahead.iter().any(|i| i.material().transparency <= 0.) {
+            return Color::black();
+        }
+        let ahead = Intersections::new(ahead);
+        // media containing the point are only looked up if they can absorb light on the way out
+        // of them, without absorption exits and entries filter the light in the same way
+        let mut inside: Vec<Intersection> = Vec::new();
+        if ahead
             .iter()
-            .map(|light| {
-                let v = &light.position - point;
-                let distance = v.mag();
-                let direction = v.unit();
-                let ray = Ray::new((*point).clone(), direction.clone());
-                let is = self.intersect(&ray);
-                let h = is.hit();
-                match h {
-                    Some(hit) => hit.t < distance,
-                    None => false,
+            .any(|i| i.material().absorption != Color::black())
+        {
+            let behind = Intersections::new(self.bvh().intersect(
+                &self.objects,
+                &ray,
+                -f64::INFINITY,
+                EPSILON_F64,
+            ));
+            for i in behind.into_iter().filter(|i| i.t <= EPSILON_F64) {
+                match inside.iter().position(|e| e.same_medium(&i)) {
+                    Some(position) => {
+                        inside.remove(position);
+                    }
+                    None => inside.push(i),
                 }
+            }
+        }
+        let mut transmittance = Color::white();
+        // media entered by the shadow ray, light is absorbed between entry and exit
+        let mut entered: Vec<&Intersection> = Vec::new();
+        for i in ahead.iter() {
+            let material = i.material();
+            transmittance = transmittance * material.transparency;
+            if let Some(position) = entered.iter().position(|e| e.same_medium(i)) {
+                let entry = entered.remove(position);
+                transmittance = transmittance * beer_lambert(material.absorption, i.t - entry.t);
+            } else if let Some(position) = inside.iter().position(|e| e.same_medium(i)) {
+                // leaving a medium containing the point, which didn't filter the light yet
+                inside.remove(position);
+                transmittance =
+                    transmittance * beer_lambert(material.absorption, i.t) * material.tint;
+            } else {
+                transmittance = transmittance * material.tint;
+                entered.push(i);
+            }
+        }
+        transmittance
+    }
+
+    /// Whether a sample of a light is completely hidden from `point`
+    pub fn is_shadowed(&self, point: &Point, sample: &LightSample, time: f64) -> bool {
+        self.transmittance(point, sample, time) == Color::black()
+    }
+
+    /// The samples of `light` for lighting `point`, each together with the fraction of each
+    /// color channel of it that reaches the point
+    pub fn light_samples(
+        &self,
+        light: &dyn Light,
+        point: &Point,
+        time: f64,
+    ) -> Vec<(LightSample, Color)> {
+        light
+            .samples(point)
+            .into_iter()
+            .map(|sample| {
+                let transmittance = self.transmittance(point, &sample, time);
+                (sample, transmittance)
             })
-            .collect::<Vec<_>>()
+            .collect()
     }
 
+    /// The fraction of each color channel of `light` that reaches `point`, averaged over the
+    /// samples of the light
+    pub fn intensity_at(&self, light: &dyn Light, point: &Point, time: f64) -> Color {
+        let samples = self.light_samples(light, point, time);
+        samples.iter().fold(Color::black(), |sum, (_, t)| sum + *t) * (1. / samples.len() as f32)
+    }
+
+    /// The color of a hit: the light emitted by the surface and the direct light of all lights
+    /// summed up, plus the light reflected and refracted by the surface
     pub fn shade_hit(&self, comp: &PreComp, remaining_recursions: usize) -> Color {
-        self.lights
+        let color = comp.surface_color();
+        let material = comp.material();
+        let surface = self
+            .lights
             .iter()
-            .zip(self.is_shadowed(&comp.over_point).into_iter())
-            .map(|(light, shadowed)| {
-                let surface = light.lighting(
-                    Arc::clone(&comp.object),
-                    &comp.object.material,
+            .map(|light| {
+                let samples = self.light_samples(light.as_ref(), &comp.over_point, comp.time);
+                light.lighting_with_samples(
+                    color,
+                    material,
                     &comp.over_point,
                     &comp.eye,
                     &comp.normal,
-                    shadowed,
-                );
-                let reflected = self.reflected_color(comp, remaining_recursions);
-                let refracted = self.refracted_color(comp, remaining_recursions);
-                let material = &comp.object.material;
-                if material.reflectiveness > 0.0 && material.transparency > 0.0 {
-                    let reflectance = comp.schlick();
-                    surface + reflected * reflectance as f32 + refracted * (1. - reflectance) as f32
-                } else {
-                    surface + reflected + refracted
-                }
-            })
-            .fold(None, |blend: Option<Color>, new_color| match blend {
-                Some(blend) => Some(new_color.blend_lighten_only(blend)),
-                None => Some(new_color),
+                    &samples,
+                )
             })
-            .unwrap()
+            .fold(Color::black(), |sum, color| sum + color);
+        let reflected = self.reflected_color(comp, remaining_recursions);
+        let refracted = self.refracted_color(comp, remaining_recursions);
+        let emitted = material.emission;
+        if material.reflectiveness > 0.0 && material.transparency > 0.0 {
+            let reflectance = comp.schlick();
+            emitted
+                + surface
+                + reflected * reflectance as f32
+                + refracted * (1. - reflectance) as f32
+        } else {
+            emitted + surface + reflected + refracted
+        }
     }
 
+    /// The color seen along a ray cast from the camera
     pub fn color_at(&self, ray: &Ray, remaining_recursions: usize) -> Color {
+        self.trace(ray, remaining_recursions, true).0
+    }
+
+    /// The background seen along a ray that missed all objects. Rays that were reflected or
+    /// refracted only see it if the background lights the scene.
+    pub fn background_at(&self, ray: &Ray, from_camera: bool) -> Color {
+        if from_camera || self.background_lighting {
+            self.background.color_at(&ray.direction)
+        } else {
+            Color::black()
+        }
+    }
+
+    /// The color seen along a ray together with the distance to the hit, infinite if the ray
+    /// misses everything
+    fn trace(&self, ray: &Ray, remaining_recursions: usize, from_camera: bool) -> (Color, f64) {
         let is = self.intersect(ray);
         match is.hit() {
             Some(hit) => {
                 let precomp = hit.prepare_computations(ray, &is);
-                self.shade_hit(&precomp, remaining_recursions)
+                (self.shade_hit(&precomp, remaining_recursions), hit.t)
             }
-            None => Color::black(),
+            None => (self.background_at(ray, from_camera), f64::INFINITY),
         }
     }
 
     /// Get the reflected color, `remaining_recursions` says how many more recursions
     /// it's allowed to make.
     pub fn reflected_color(&self, comps: &PreComp, remaining_recursions: usize) -> Color {
-        if remaining_recursions == 0 || comps.object.material.reflectiveness.approx_eq(0.0) {
+        if remaining_recursions == 0 || comps.material().reflectiveness.approx_eq(0.0) {
             Color::black()
         } else {
-            let reflect_ray = Ray::new(comps.over_point.clone(), comps.reflection.clone());
-            let color = self.color_at(&reflect_ray, remaining_recursions - 1);
-            color * comps.object.material.reflectiveness
+            let reflect_ray = Ray::new_with_time(
+                comps.over_point.clone(),
+                comps.reflection.clone(),
+                comps.time,
+            );
+            let (color, distance) = self.trace(&reflect_ray, remaining_recursions - 1, false);
+            // reflected rays stay in the medium the ray came through, like inside of glass
+            color * beer_lambert(comps.absorption1, distance) * comps.material().reflectiveness
         }
     }
 
     pub fn refracted_color(&self, comps: &PreComp, remaining_recursions: usize) -> Color {
-        if remaining_recursions == 0 || comps.object.material.transparency.approx_eq(0.0) {
+        if remaining_recursions == 0 || comps.material().transparency.approx_eq(0.0) {
             Color::black()
         } else {
-            // Handle total internal reflection. Implementation based on Snell's Law
-            let n_ratio = comps.n1 / comps.n2;
-            let cos_i = (&comps.eye).scalar_prod(&comps.normal);
-            let sin2_t = n_ratio.powi(2) as f64 * (1. - cos_i.powi(2));
-            if sin2_t > 1.0 {
-                Color::black()
-            } else {
-                let n_ratio = n_ratio as f64;
-                let cos_t = (1.0 - sin2_t).sqrt();
-                let direction =
-                    comps.normal.clone() * (n_ratio * cos_i - cos_t) - comps.eye.clone() * n_ratio;
-                let refract_ray = Ray::new(comps.under_point.clone(), direction);
-                self.color_at(&refract_ray, remaining_recursions - 1)
-                    * comps.object.material.transparency
+            match comps.refraction() {
+                // total internal reflection
+                None => Color::black(),
+                Some(direction) => {
+                    let refract_ray =
+                        Ray::new_with_time(comps.under_point.clone(), direction, comps.time);
+                    let (color, distance) =
+                        self.trace(&refract_ray, remaining_recursions - 1, false);
+                    color
+                        * beer_lambert(comps.absorption2, distance)
+                        * comps.material().transparency
+                }
             }
         }
     }
@@ -137,7 +285,7 @@ impl Default for World {
     fn default() -> Self {
         let light = PointLight::new(point(-10., 10., -10.), Color::new_rgb(1., 1., 1.));
         let s1 = Shape::new(
-            SPHERE,
+            Sphere,
             Material::new(
                 Color::new_rgb(0.8, 1.0, 0.6),
                 0.1,
@@ -152,6 +300,6 @@ impl Default for World {
         );
         let mut s2 = Shape::default();
         s2.modify_transform(|t| t.scale(0.5, 0.5, 0.5));
-        World::new(vec![s1, s2], vec![light])
+        World::new(vec![s1, s2], vec![Box::new(light)])
     }
 }
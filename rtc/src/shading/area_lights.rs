@@ -0,0 +1,351 @@
+use std::{f64::consts::PI, fmt};
+
+use crate::{
+    primitives::{
+        approx_eq::EPSILON_F64,
+        vector::{point, vector, CrossProd, Point, ScalarProd, Vec3D},
+    },
+    shapes::{Rectangle, Shape, SmoothTriangle, Triangle},
+    utils::random::Rng,
+};
+
+use super::{Attenuation, Color, Light, LightSample};
+
+/// Random numbers for jittering the samples of a light as seen from `point`. Seeding with the
+/// point makes the samples reproducible, so shadows and shading agree on them.
+fn jitter_rng(point: &Point) -> Rng {
+    Rng::from_seeds(&[
+        point.x().to_bits(),
+        point.y().to_bits(),
+        point.z().to_bits(),
+    ])
+}
+
+/// Offsets of the samples within the cells of a `usteps` by `vsteps` grid, each between 0 and 1
+fn cell_offsets(usteps: usize, vsteps: usize, jitter: bool, point: &Point) -> Vec<(f64, f64)> {
+    let mut rng = jitter_rng(point);
+    (0..vsteps)
+        .flat_map(|v| (0..usteps).map(move |u| (u, v)))
+        .map(|(u, v)| {
+            let (du, dv) = if jitter {
+                (rng.next_f64(), rng.next_f64())
+            } else {
+                (0.5, 0.5)
+            };
+            (
+                (u as f64 + du) / usteps as f64,
+                (v as f64 + dv) / vsteps as f64,
+            )
+        })
+        .collect()
+}
+
+/// A rectangular light spanned by two edges starting at a corner. The rectangle is split into
+/// `usteps` by `vsteps` cells with one sample each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RectangleLight {
+    pub corner: Point,
+    pub uvec: Vec3D,
+    pub usteps: usize,
+    pub vvec: Vec3D,
+    pub vsteps: usize,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    /// Place samples randomly within their cell rather than at its center, this trades banding
+    /// in the penumbra for noise
+    pub jitter: bool,
+}
+
+impl RectangleLight {
+    pub fn new(
+        corner: Point,
+        uvec: Vec3D,
+        usteps: usize,
+        vvec: Vec3D,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        assert!(
+            usteps > 0 && vsteps > 0,
+            "Area lights need at least one sample."
+        );
+        RectangleLight {
+            corner,
+            uvec,
+            usteps,
+            vvec,
+            vsteps,
+            intensity,
+            attenuation: Attenuation::None,
+            jitter: true,
+        }
+    }
+
+    /// Point on the light at the surface coordinates (u, v), both between 0 and 1
+    pub fn point_on_light(&self, u: f64, v: f64) -> Point {
+        &self.corner + &self.uvec * u + &self.vvec * v
+    }
+}
+
+impl Light for RectangleLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn ambient(&self, point: &Point) -> Color {
+        let distance = (&self.point_on_light(0.5, 0.5) - point).mag();
+        self.intensity * self.attenuation.factor(distance)
+    }
+
+    fn samples(&self, point: &Point) -> Vec<LightSample> {
+        cell_offsets(self.usteps, self.vsteps, self.jitter, point)
+            .into_iter()
+            .map(|(u, v)| {
+                LightSample::from_position(
+                    point,
+                    &self.point_on_light(u, v),
+                    self.intensity,
+                    self.attenuation,
+                )
+            })
+            .collect()
+    }
+}
+
+/// A spherical light. Seen from any point it looks like a disk, which is sampled on a grid of
+/// `steps` rings by `steps` sectors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SphereLight {
+    pub center: Point,
+    pub radius: f64,
+    pub steps: usize,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+    /// Place samples randomly within their cell rather than at its center
+    pub jitter: bool,
+}
+
+impl SphereLight {
+    pub fn new(center: Point, radius: f64, steps: usize, intensity: Color) -> Self {
+        assert!(steps > 0, "Area lights need at least one sample.");
+        SphereLight {
+            center,
+            radius,
+            steps,
+            intensity,
+            attenuation: Attenuation::None,
+            jitter: true,
+        }
+    }
+
+    /// A sphere emitting `power` (in W) of light of some color evenly into all directions, its
+    /// light falls off with the squared distance like that of a point light
+    pub fn with_power(center: Point, radius: f64, steps: usize, color: Color, power: f64) -> Self {
+        SphereLight {
+            attenuation: Attenuation::InverseSquare,
+            ..Self::new(center, radius, steps, color * (power / (4. * PI)) as f32)
+        }
+    }
+}
+
+impl Light for SphereLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn ambient(&self, point: &Point) -> Color {
+        let distance = (&self.center - point).mag();
+        self.intensity * self.attenuation.factor(distance)
+    }
+
+    fn samples(&self, point: &Point) -> Vec<LightSample> {
+        // the disk facing `point` that the sphere covers
+        let to_point = point - &self.center;
+        let w = if to_point.clone().mag() > 0. {
+            to_point.unit()
+        } else {
+            vector(0., 1., 0.)
+        };
+        let helper = if w.x().abs() < 0.9 {
+            vector(1., 0., 0.)
+        } else {
+            vector(0., 1., 0.)
+        };
+        let u = (&helper).cross(&w).unit();
+        let v = (&w).cross(&u);
+        cell_offsets(self.steps, self.steps, self.jitter, point)
+            .into_iter()
+            .map(|(s, t)| {
+                // taking the square root spreads the samples evenly over the area of the disk
+                let r = self.radius * s.sqrt();
+                let angle = 2. * PI * t;
+                let position = &self.center + &u * (r * angle.cos()) + &v * (r * angle.sin());
+                LightSample::from_position(point, &position, self.intensity, self.attenuation)
+            })
+            .collect()
+    }
+}
+
+/// Why a shape can't be turned into a `MeshLight`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshLightError {
+    /// Neither the shape nor its descendants are triangles or rectangles with an area, e.g. a
+    /// sphere
+    NothingToSample,
+    /// The light was asked for zero samples
+    NoSamples,
+}
+
+impl fmt::Display for MeshLightError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshLightError::NothingToSample => {
+                write!(f, "mesh lights need triangles or rectangles to sample")
+            }
+            MeshLightError::NoSamples => write!(f, "mesh lights need at least one sample"),
+        }
+    }
+}
+
+/// The surface of an emissive shape used as a light, so it lights the scene and casts soft
+/// shadows. Triangles, smooth triangles and rectangles are sampled, also within groups, each
+/// emits the light of its own material. Other shapes within groups are skipped, they still glow
+/// but don't light the scene. The shape has to be added to the world as well to be visible, see
+/// `World::add_mesh_light`.
+#[derive(Debug, Clone)]
+pub struct MeshLight {
+    /// World space corners of the triangles of the surface together with their emission
+    triangles: Vec<([Point; 3], Color)>,
+    /// Running total of the areas of the triangles
+    cumulative_areas: Vec<f64>,
+    pub samples: usize,
+    /// Place samples randomly rather than at fixed points of the triangles
+    pub jitter: bool,
+}
+
+impl MeshLight {
+    /// A light sampling the surface of `shape` with `samples` points. Degenerate triangles
+    /// without an area are skipped. Fails if there are no samples or nothing is left to sample.
+    pub fn new(shape: &Shape, samples: usize) -> Result<Self, MeshLightError> {
+        if samples == 0 {
+            return Err(MeshLightError::NoSamples);
+        }
+        let mut triangles = Vec::new();
+        collect_triangles(shape, &mut triangles);
+        let area = |[p1, p2, p3]: &[Point; 3]| (p2 - p1).cross(&(p3 - p1)).mag() / 2.;
+        triangles.retain(|(corners, _)| area(corners) > 0.);
+        if triangles.is_empty() {
+            return Err(MeshLightError::NothingToSample);
+        }
+        let cumulative_areas = triangles
+            .iter()
+            .scan(0., |total, (corners, _)| {
+                *total += area(corners);
+                Some(*total)
+            })
+            .collect();
+        Ok(MeshLight {
+            triangles,
+            cumulative_areas,
+            samples,
+            jitter: true,
+        })
+    }
+
+    /// The area of the surface
+    pub fn area(&self) -> f64 {
+        *self.cumulative_areas.last().unwrap()
+    }
+}
+
+/// Gather the world space triangles of `shape` and its descendants together with their emission,
+/// rectangles are split into two
+fn collect_triangles(shape: &Shape, triangles: &mut Vec<([Point; 3], Color)>) {
+    let transform = shape.world_transform();
+    let corners = if let Some(t) = shape.geometry_as::<Triangle>() {
+        vec![[t.p1.clone(), t.p2.clone(), t.p3.clone()]]
+    } else if let Some(t) = shape.geometry_as::<SmoothTriangle>() {
+        let t = &t.triangle;
+        vec![[t.p1.clone(), t.p2.clone(), t.p3.clone()]]
+    } else if let Some(r) = shape.geometry_as::<Rectangle>() {
+        let (x, z) = (r.width / 2., r.depth / 2.);
+        vec![
+            [point(-x, 0., -z), point(x, 0., -z), point(x, 0., z)],
+            [point(-x, 0., -z), point(x, 0., z), point(-x, 0., z)],
+        ]
+    } else {
+        vec![]
+    };
+    triangles.extend(
+        corners
+            .into_iter()
+            .map(|corners| (corners.map(|p| &transform * &p), shape.material.emission)),
+    );
+    for child in shape.children() {
+        collect_triangles(child, triangles);
+    }
+}
+
+impl Light for MeshLight {
+    /// The emission averaged over the surface
+    fn intensity(&self) -> Color {
+        let mut previous = 0.;
+        self.triangles
+            .iter()
+            .zip(self.cumulative_areas.iter())
+            .fold(Color::black(), |sum, ((_, emission), &total)| {
+                let area = total - previous;
+                previous = total;
+                sum + *emission * area as f32
+            })
+            * (1. / self.area() as f32)
+    }
+
+    /// The light arriving at `point`
+    fn ambient(&self, point: &Point) -> Color {
+        self.samples(point)
+            .iter()
+            .fold(Color::black(), |sum, sample| sum + sample.intensity)
+            * (1. / self.samples as f32)
+    }
+
+    fn is_emissive_shape(&self) -> bool {
+        true
+    }
+
+    fn samples(&self, point: &Point) -> Vec<LightSample> {
+        let mut rng = jitter_rng(point);
+        let area = self.area();
+        let n = self.samples as f64;
+        (0..self.samples)
+            .map(|i| {
+                let (s, a, b) = if self.jitter {
+                    (rng.next_f64(), rng.next_f64(), rng.next_f64())
+                } else {
+                    (0.5, 0.5, 0.5)
+                };
+                // stratify the samples by area over the triangles
+                let target = (i as f64 + s) / n * area;
+                let index = self
+                    .cumulative_areas
+                    .partition_point(|&total| total <= target)
+                    .min(self.triangles.len() - 1);
+                let ([p1, p2, p3], emission) = &self.triangles[index];
+                // uniformly distributed barycentric coordinates
+                let r = a.sqrt();
+                let position = p1 * (1. - r) + p2 * (r * (1. - b)) + p3 * (r * b);
+                let mut sample =
+                    LightSample::from_position(point, &position, *emission, Attenuation::None);
+                let normal = (p2 - p1).cross(&(p3 - p1)).unit();
+                let cos = (&normal).scalar_prod(&sample.direction).abs();
+                // the share of the surface covered by the sample seen from `point`, divided by π
+                // so that lit surfaces look the same as in the path tracer
+                let d = sample.distance;
+                sample.intensity = sample.intensity * (cos * area / (PI * d * d)) as f32;
+                // keep shadow rays from hitting the surface at the sample itself
+                sample.distance -= EPSILON_F64;
+                sample
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,64 @@
+//! Physically based microfacet reflection after Cook and Torrance, with the GGX distribution of
+//! microfacet normals, Smith's shadowing term and Schlick's approximation of the Fresnel term.
+//! See "Real Shading in Unreal Engine 4" by Brian Karis for the choice of the approximations.
+
+use std::f32::consts::PI;
+
+use crate::primitives::vector::{ScalarProd, Vec3D};
+
+use super::Color;
+
+/// Reflectance of dielectrics at normal incidence, most of them lie close to 4%
+const DIELECTRIC_F0: f32 = 0.04;
+
+/// Light reflected towards `eye` from light arriving from `light_v`, per unit of incoming light.
+/// This is the BRDF times the cosine of the angle of incidence. All vectors are unit vectors
+/// pointing away from the surface. Lights multiply this by π, as their intensities follow the
+/// convention of the Phong model (see `Light`).
+pub fn cook_torrance(
+    color: Color,
+    metallic: f32,
+    roughness: f32,
+    normal: &Vec3D,
+    eye: &Vec3D,
+    light_v: &Vec3D,
+) -> Color {
+    let n_dot_l = normal.scalar_prod(light_v) as f32;
+    let n_dot_v = normal.scalar_prod(eye) as f32;
+    if n_dot_l <= 0. || n_dot_v <= 0. {
+        return Color::black();
+    }
+    let halfway = (eye + light_v).unit();
+    let n_dot_h = (normal.scalar_prod(&halfway) as f32).max(0.);
+    let v_dot_h = (eye.scalar_prod(&halfway) as f32).max(0.);
+
+    let d = ggx_distribution(n_dot_h, roughness);
+    let g = smith_geometry(n_dot_v, roughness) * smith_geometry(n_dot_l, roughness);
+    let f0 = Color::white() * (DIELECTRIC_F0 * (1. - metallic)) + color * metallic;
+    let f = schlick_fresnel(f0, v_dot_h);
+
+    let specular = f * (d * g / (4. * n_dot_l * n_dot_v));
+    // light that isn't reflected at the surface enters it and is scattered diffusely, unless
+    // it is absorbed by a metal
+    let diffuse = (Color::white() - f) * color * ((1. - metallic) / PI);
+    (diffuse + specular) * n_dot_l
+}
+
+/// Density of microfacets oriented along the halfway vector
+fn ggx_distribution(n_dot_h: f32, roughness: f32) -> f32 {
+    // perfectly smooth surfaces would make the distribution a spike
+    let alpha = (roughness * roughness).max(1e-3);
+    let alpha2 = alpha * alpha;
+    let denom = n_dot_h * n_dot_h * (alpha2 - 1.) + 1.;
+    alpha2 / (PI * denom * denom)
+}
+
+/// Fraction of microfacets visible from a direction at angle cos⁻¹(`n_dot_x`) to the normal
+fn smith_geometry(n_dot_x: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.).powi(2) / 8.;
+    n_dot_x / (n_dot_x * (1. - k) + k)
+}
+
+fn schlick_fresnel(f0: Color, v_dot_h: f32) -> Color {
+    f0 + (Color::white() - f0) * (1. - v_dot_h).powi(5)
+}
@@ -1,71 +1,394 @@
-use std::sync::Arc;
+use std::{f64::consts::PI, fmt, sync::Arc};
 
 use crate::{
-    primitives::vector::{Point, ScalarProd, Vec3D},
+    primitives::{
+        approx_eq::EPSILON_F64,
+        vector::{Point, ScalarProd, Vec3D},
+    },
     shapes::Shape,
 };
 
-use super::{Color, Material};
+use super::{cook_torrance, Color, Material, ShadingModel};
 
+/// How the light of a light source falls off with the distance to it
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Attenuation {
+    /// Equally bright at every distance
+    #[default]
+    None,
+    /// Brightness is divided by the distance
+    Linear,
+    /// Brightness is divided by the squared distance, the physically correct falloff of light
+    /// spreading from a point
+    InverseSquare,
+}
+
+impl Attenuation {
+    /// Factor applied to the intensity of a light `distance` units away. Distances are clamped
+    /// to `EPSILON_F64`, so points on top of a light stay finitely bright.
+    pub fn factor(&self, distance: f64) -> f32 {
+        let distance = distance.max(EPSILON_F64);
+        match self {
+            Attenuation::None => 1.,
+            Attenuation::Linear => 1. / distance as f32,
+            Attenuation::InverseSquare => 1. / (distance * distance) as f32,
+        }
+    }
+}
+
+/// Light reaching a point from one sample of a light
 #[derive(Debug, Clone, PartialEq)]
-pub struct PointLight {
+pub struct LightSample {
+    /// Unit vector from the lit point towards the light
+    pub direction: Vec3D,
+    /// Distance from the lit point to the light, infinite for directional lights
+    pub distance: f64,
+    /// Color and brightness of the light arriving at the lit point
     pub intensity: Color,
-    pub position: Point,
 }
 
-impl PointLight {
-    pub fn new(position: Point, intensity: Color) -> Self {
-        PointLight {
-            intensity,
-            position,
+impl LightSample {
+    /// Light arriving at `point` from a sample at `position` whose intensity falls off with
+    /// `attenuation`
+    pub fn from_position(
+        point: &Point,
+        position: &Point,
+        intensity: Color,
+        attenuation: Attenuation,
+    ) -> Self {
+        let v = position - point;
+        let distance = v.clone().mag();
+        LightSample {
+            distance,
+            direction: v.unit(),
+            intensity: intensity * attenuation.factor(distance),
         }
     }
+}
 
-    /// Calculate the color of some point in space
-    pub fn lighting(
+/// A source of light. Lights are sampled one or more times, shadows are soft if only some of the
+/// samples are visible from the lit point.
+///
+/// Intensities follow the convention of the Phong model: a white, perfectly diffuse surface
+/// facing a light of intensity 1 reflects 1, rather than the 1/π a physically based BRDF
+/// yields. Every shading model is scaled to this convention, so materials can be switched per
+/// object without changing the brightness of the scene. Lights sampling emissive surfaces
+/// divide by π to match, see `MeshLight`.
+pub trait Light: fmt::Debug + Send + Sync {
+    /// Color and brightness of the light
+    fn intensity(&self) -> Color;
+
+    /// Light contributing to the ambient lighting of `point`
+    fn ambient(&self, _point: &Point) -> Color {
+        self.intensity()
+    }
+
+    /// Whether the light samples the surface of an emissive shape in the scene. The path tracer
+    /// skips these lights, it gathers the light of emissive shapes by hitting them.
+    fn is_emissive_shape(&self) -> bool {
+        false
+    }
+
+    /// Samples of the light that are checked for visibility when lighting `point`. The samples
+    /// only depend on the light and `point`, so calling this twice yields the same samples.
+    fn samples(&self, point: &Point) -> Vec<LightSample>;
+
+    /// Calculate the color of some point in space. `intensity` is the fraction of the light that
+    /// reaches the point, 0 if it lies in full shadow.
+    fn lighting(
         &self,
         object: Arc<Shape>,
         material: &Material,
         point: &Point,
         eye: &Vec3D,
         normal: &Vec3D,
-        in_shadow: bool,
+        intensity: f32,
     ) -> Color {
         let color = if let Some(pattern) = &material.pattern {
             pattern.at(object, point)
         } else {
             material.color
         };
-        let effective_color = color * self.intensity;
-        let ambient = effective_color * material.ambient;
-        if in_shadow {
-            ambient
-        } else {
-            let light_v = (&self.position - point).unit();
-            let light_dot_normal: f32 = (&light_v).scalar_prod(normal) as f32;
-            let (diffuse, specular) = if light_dot_normal < 0.0 {
-                let diffuse = Color::black();
-                let specular = Color::black();
-                (diffuse, specular)
-            } else {
-                let diffuse = effective_color * material.diffuse * light_dot_normal;
-                let reflect_v = (-light_v).reflect(normal);
-                let reflect_dot_eye = reflect_v.scalar_prod(eye);
-                let specular = if reflect_dot_eye <= 0.0 {
-                    Color::black()
-                } else {
-                    let factor: f32 = (reflect_dot_eye as f32).powf(material.shininess);
-                    self.intensity * material.specular * factor
+        self.lighting_with_color(
+            color,
+            material,
+            point,
+            eye,
+            normal,
+            Color::white() * intensity,
+        )
+    }
+
+    /// Calculate the color of some point in space whose surface color is already known.
+    /// `transmittance` is the fraction of each color channel of the light that reaches the point,
+    /// it is tinted by transparent objects in the way.
+    fn lighting_with_color(
+        &self,
+        color: Color,
+        material: &Material,
+        point: &Point,
+        eye: &Vec3D,
+        normal: &Vec3D,
+        transmittance: Color,
+    ) -> Color {
+        let samples: Vec<_> = self
+            .samples(point)
+            .into_iter()
+            .map(|sample| (sample, transmittance))
+            .collect();
+        self.lighting_with_samples(color, material, point, eye, normal, &samples)
+    }
+
+    /// Calculate the color of some point in space from samples of this light that were already
+    /// taken, each together with the fraction of each color channel of it reaching the point.
+    fn lighting_with_samples(
+        &self,
+        color: Color,
+        material: &Material,
+        point: &Point,
+        eye: &Vec3D,
+        normal: &Vec3D,
+        samples: &[(LightSample, Color)],
+    ) -> Color {
+        let ambient = color * self.ambient(point) * material.ambient;
+        ambient + self.direct_lighting(color, material, eye, normal, samples)
+    }
+
+    /// The light of this light reflected towards the eye, without the ambient term. `samples`
+    /// holds the samples of the light together with the fraction of each that reaches the point.
+    /// Diffuse and specular light are averaged over all samples.
+    fn direct_lighting(
+        &self,
+        color: Color,
+        material: &Material,
+        eye: &Vec3D,
+        normal: &Vec3D,
+        samples: &[(LightSample, Color)],
+    ) -> Color {
+        let lit = samples
+            .iter()
+            .filter(|(_, transmittance)| *transmittance != Color::black())
+            .map(|(sample, transmittance)| {
+                let reflected = match material.model {
+                    ShadingModel::Phong => phong(color, material, sample, eye, normal),
+                    ShadingModel::CookTorrance {
+                        metallic,
+                        roughness,
+                    } => {
+                        // the BRDF is scaled by π to the convention of Phong, see above
+                        sample.intensity
+                            * cook_torrance(
+                                color,
+                                metallic,
+                                roughness,
+                                normal,
+                                eye,
+                                &sample.direction,
+                            )
+                            * PI as f32
+                    }
                 };
-                (diffuse, specular)
-            };
-            ambient + diffuse + specular
+                reflected * *transmittance
+            })
+            .fold(Color::black(), |sum, color| sum + color);
+        lit * (1. / samples.len() as f32)
+    }
+}
+
+/// Diffuse and specular light of one sample of a light after Phong
+fn phong(
+    color: Color,
+    material: &Material,
+    sample: &LightSample,
+    eye: &Vec3D,
+    normal: &Vec3D,
+) -> Color {
+    let light_v = &sample.direction;
+    let light_dot_normal: f32 = light_v.scalar_prod(normal) as f32;
+    if light_dot_normal < 0.0 {
+        return Color::black();
+    }
+    let diffuse = color * sample.intensity * material.diffuse * light_dot_normal;
+    let reflect_v = (-light_v).reflect(normal);
+    let reflect_dot_eye = reflect_v.scalar_prod(eye);
+    let specular = if reflect_dot_eye <= 0.0 {
+        Color::black()
+    } else {
+        let factor: f32 = (reflect_dot_eye as f32).powf(material.shininess);
+        sample.intensity * material.specular * factor
+    };
+    diffuse + specular
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointLight {
+    pub intensity: Color,
+    pub position: Point,
+    pub attenuation: Attenuation,
+}
+
+impl PointLight {
+    /// A light with the same intensity at every distance
+    pub fn new(position: Point, intensity: Color) -> Self {
+        PointLight {
+            intensity,
+            position,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    /// A light emitting `power` (in W) of light of some color evenly into all directions. Its
+    /// intensity falls off with the squared distance, so a surface `d` units away facing the
+    /// light receives `power / (4π d²)`.
+    pub fn with_power(position: Point, color: Color, power: f64) -> Self {
+        PointLight {
+            intensity: color * (power / (4. * PI)) as f32,
+            position,
+            attenuation: Attenuation::InverseSquare,
         }
     }
 }
 
+impl Light for PointLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn ambient(&self, point: &Point) -> Color {
+        let distance = (&self.position - point).mag();
+        self.intensity * self.attenuation.factor(distance)
+    }
+
+    fn samples(&self, point: &Point) -> Vec<LightSample> {
+        vec![LightSample::from_position(
+            point,
+            &self.position,
+            self.intensity,
+            self.attenuation,
+        )]
+    }
+}
+
 impl Default for PointLight {
     fn default() -> Self {
         PointLight::new(Point::origin(), Color::default())
     }
 }
+
+/// A point light that only shines into a cone around `direction`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotLight {
+    pub position: Point,
+    /// Unit vector the spot light points at
+    pub direction: Vec3D,
+    /// Angle between the axis and the edge of the cone in rad
+    pub cone_angle: f64,
+    /// How fast the light fades from the axis to the edge of the cone. 0 gives a uniformly lit
+    /// cone, larger values a softer edge.
+    pub falloff: f32,
+    pub intensity: Color,
+    pub attenuation: Attenuation,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point,
+        direction: Vec3D,
+        cone_angle: f64,
+        falloff: f32,
+        intensity: Color,
+    ) -> Self {
+        SpotLight {
+            position,
+            direction: direction.unit(),
+            cone_angle,
+            falloff,
+            intensity,
+            attenuation: Attenuation::None,
+        }
+    }
+
+    /// A spot light emitting `power` (in W) of light of some color into its cone. The light
+    /// falls off with the squared distance like that of a point light.
+    pub fn with_power(
+        position: Point,
+        direction: Vec3D,
+        cone_angle: f64,
+        falloff: f32,
+        color: Color,
+        power: f64,
+    ) -> Self {
+        // the solid angle covered by the cone
+        let solid_angle = 2. * PI * (1. - cone_angle.cos());
+        SpotLight {
+            attenuation: Attenuation::InverseSquare,
+            ..Self::new(
+                position,
+                direction,
+                cone_angle,
+                falloff,
+                color * (power / solid_angle) as f32,
+            )
+        }
+    }
+
+    /// Fraction of the light that reaches a point seen from the light in direction `to_point`
+    pub fn cone_factor(&self, to_point: &Vec3D) -> f32 {
+        let cos_angle = to_point.scalar_prod(&self.direction);
+        let cos_cone = self.cone_angle.cos();
+        if cos_angle <= cos_cone {
+            0.
+        } else {
+            (((cos_angle - cos_cone) / (1. - cos_cone)) as f32).powf(self.falloff)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn ambient(&self, point: &Point) -> Color {
+        let distance = (&self.position - point).mag();
+        self.intensity * self.attenuation.factor(distance)
+    }
+
+    fn samples(&self, point: &Point) -> Vec<LightSample> {
+        let mut sample =
+            LightSample::from_position(point, &self.position, self.intensity, self.attenuation);
+        sample.intensity = sample.intensity * self.cone_factor(&-sample.direction.clone());
+        vec![sample]
+    }
+}
+
+/// A light infinitely far away like the sun, all its rays are parallel
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectionalLight {
+    /// Unit vector in which the light travels
+    pub direction: Vec3D,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Vec3D, intensity: Color) -> Self {
+        DirectionalLight {
+            direction: direction.unit(),
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, _point: &Point) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: -self.direction.clone(),
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+        }]
+    }
+}
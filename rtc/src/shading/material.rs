@@ -2,6 +2,18 @@ use crate::primitives::approx_eq::ApproxEq;
 
 use super::{Color, Pattern};
 
+/// How a material reflects the light of light sources
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ShadingModel {
+    /// Phong's model, using the `diffuse`, `specular` and `shininess` of the material
+    #[default]
+    Phong,
+    /// Physically based microfacet model with the material's color as base color. `metallic`
+    /// blends between a dielectric (0) and a metal (1), `roughness` between a mirror-like (0) and
+    /// a completely matte (1) surface.
+    CookTorrance { metallic: f32, roughness: f32 },
+}
+
 #[derive(Debug, Clone)]
 pub struct Material {
     pub color: Color,
@@ -16,9 +28,21 @@ pub struct Material {
     pub transparency: f32,
     /// 1 means "empty", vacuum like behaviour
     pub refractive_index: f32,
+    /// Color filter applied to light passing through a transparent material, this colors the
+    /// shadows it casts. White lets all colors pass.
+    pub tint: Color,
+    /// How much of each color channel is absorbed per unit of distance travelled inside the
+    /// material. Black absorbs nothing, so thick and thin glass look alike.
+    pub absorption: Color,
+    pub model: ShadingModel,
+    /// Light given off by the surface itself, it glows even without any lights. Scale the color
+    /// for a brighter glow.
+    pub emission: Color,
 }
 
 impl Material {
+    // one argument per field of the Phong model, like in the book
+    #[allow(clippy::too_many_arguments)]
     pub fn new_with_pattern(
         color: Color,
         pattern: Option<Pattern>,
@@ -40,9 +64,15 @@ impl Material {
             reflectiveness,
             transparency,
             refractive_index,
+            tint: Color::white(),
+            absorption: Color::black(),
+            model: ShadingModel::Phong,
+            emission: Color::black(),
         }
     }
 
+    // one argument per field of the Phong model, like in the book
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         color: Color,
         ambient: f32,
@@ -65,6 +95,39 @@ impl Material {
             refractive_index,
         )
     }
+
+    /// A physically based material with metallic/roughness parameters as used by common PBR
+    /// workflows
+    pub fn new_pbr(color: Color, metallic: f32, roughness: f32) -> Self {
+        Material {
+            model: ShadingModel::CookTorrance {
+                metallic: metallic.clamp(0., 1.),
+                roughness: roughness.clamp(0., 1.),
+            },
+            ..Material::new(color, 0.1, 0., 0., 0., 0., 0., 1.)
+        }
+    }
+
+    /// A material that glows in some color with the given strength and doesn't reflect any
+    /// light, e.g. for lamps and screens
+    pub fn new_emissive(color: Color, strength: f32) -> Self {
+        Material {
+            emission: color * strength,
+            ..Material::new(Color::black(), 0., 0., 0., 200., 0., 0., 1.)
+        }
+    }
+}
+
+/// Fraction of each color channel that is left after light travels `distance` through a medium
+/// with the given absorption, following the Beer–Lambert law
+pub fn beer_lambert(absorption: Color, distance: f64) -> Color {
+    absorption.map(|a| {
+        if a > 0. {
+            (-a * distance as f32).exp()
+        } else {
+            1.
+        }
+    })
 }
 
 impl Default for Material {
@@ -2,17 +2,25 @@ use crate::primitives::pixel::Pixel;
 
 pub type Color = Pixel;
 
+pub use area_lights::*;
+pub use brdf::*;
+// only holds constructors on `Material` for now
+#[allow(unused_imports)]
 pub use builtin_materials::*;
 pub use builtin_patterns::*;
 pub use lights::*;
 pub use material::*;
 pub use pattern::*;
+pub use texture::*;
 
+mod area_lights;
+mod brdf;
 mod builtin_materials;
 mod builtin_patterns;
 mod lights;
 mod material;
 mod pattern;
+mod texture;
 
 #[cfg(test)]
 mod tests;
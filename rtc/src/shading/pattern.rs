@@ -8,15 +8,22 @@ use crate::{
     shapes::Shape,
 };
 
-use super::Color;
+use super::{Color, ImageTexture};
 
 pub type PatternFunc = fn(&Point) -> Color;
 
+/// What gives a pattern its colors
+#[derive(Clone)]
+enum Source {
+    Function(PatternFunc),
+    Texture(ImageTexture),
+}
+
 #[derive(Clone)]
 pub struct Pattern {
     transform: Transformation,
     inverse_transform: Transformation,
-    pattern_function: PatternFunc,
+    source: Source,
 }
 
 impl fmt::Debug for Pattern {
@@ -31,22 +38,53 @@ impl fmt::Debug for Pattern {
 
 impl Pattern {
     pub fn new(pattern_function: PatternFunc, transform: Transformation) -> Self {
+        Self::with_source(Source::Function(pattern_function), transform)
+    }
+
+    /// A pattern showing an image, the transformation places the image on the object
+    pub fn new_texture(texture: ImageTexture, transform: Transformation) -> Self {
+        Self::with_source(Source::Texture(texture), transform)
+    }
+
+    fn with_source(source: Source, transform: Transformation) -> Self {
         let inverse_transform = transform
             .invert()
             .expect("Encountered non invertible matrix.");
         Pattern {
             transform,
             inverse_transform,
-            pattern_function,
+            source,
         }
     }
 
     /// Find the color of the pattern given a point and the associated object
     /// We first transform the point to object space and then to pattern space
     pub fn at(&self, object: Arc<Shape>, point: &Point) -> Color {
-        let object_point = object.inverse_transform() * point;
+        self.at_object_point(&object.world_to_object(point))
+    }
+
+    /// Find the color of the pattern at a point given in object space. Textures using the
+    /// surface coordinates of the hit are sampled at (0, 0), see `at_surface_point`.
+    pub fn at_object_point(&self, object_point: &Point) -> Color {
+        self.at_surface_point(object_point, (0., 0.))
+    }
+
+    /// Find the color of the pattern at a point given in object space whose surface coordinates
+    /// are `uv`
+    pub fn at_surface_point(&self, object_point: &Point, uv: (f64, f64)) -> Color {
         let pattern_point = &self.inverse_transform * object_point;
-        (self.pattern_function)(&pattern_point)
+        match &self.source {
+            Source::Function(pattern_function) => pattern_function(&pattern_point),
+            Source::Texture(texture) => texture.at(&pattern_point, uv),
+        }
+    }
+
+    /// The texture shown by the pattern, if any
+    pub fn texture(&self) -> Option<&ImageTexture> {
+        match &self.source {
+            Source::Texture(texture) => Some(texture),
+            Source::Function(_) => None,
+        }
     }
 
     pub fn transform(&self) -> &Transformation {
@@ -1,13 +1,22 @@
+// materials are set up one field at a time, like in the book
+#![allow(clippy::field_reassign_with_default)]
+
 use std::{f64::consts, sync::Arc};
 
 use crate::{
     assert_approx_eq,
-    primitives::vector::{point, vector, Point, Transformation},
+    primitives::{
+        approx_eq::EPSILON_F64,
+        canvas::Canvas,
+        vector::{point, vector, Point, ScalarProd, Transformation, Vec3D},
+    },
     shapes::Shape,
 };
 
 use super::*;
 
+// kept as originally written, the clone is redundant for Copy colors
+#[allow(clippy::clone_on_copy)]
 #[test]
 fn new() {
     let i = Color::new_rgb(1., 1., 1.);
@@ -25,7 +34,7 @@ fn lighting_eye_between() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 0., -10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, false);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 1.0);
     assert_approx_eq!(result, Color::new_rgb(1.9, 1.9, 1.9));
 }
 
@@ -38,7 +47,7 @@ fn lighting_eye_up() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 0., -10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, false);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 1.0);
     assert_approx_eq!(result, Color::new_rgb(1.0, 1.0, 1.0));
 }
 
@@ -50,7 +59,7 @@ fn lighting_light_up() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 10., -10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, false);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 1.0);
     assert_approx_eq!(result, Color::new_rgb(0.7364, 0.7364, 0.7364));
 }
 
@@ -63,7 +72,7 @@ fn lighting_light_up_eye_down() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 10., -10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, false);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 1.0);
     assert_approx_eq!(result, Color::new_rgb(1.6364, 1.6364, 1.6364));
 }
 
@@ -75,7 +84,7 @@ fn lighting_light_behind() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 0., 10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, false);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 1.0);
     assert_approx_eq!(result, Color::new_rgb(0.1, 0.1, 0.1));
 }
 
@@ -87,7 +96,7 @@ fn lighting_surface_in_shadow() {
     let normal = vector(0., 0., -1.);
     let light = PointLight::new(point(0., 0., -10.), Color::new_rgb(1., 1., 1.));
     let object = Arc::new(Shape::default());
-    let result = light.lighting(object, &m, &position, &eye, &normal, true);
+    let result = light.lighting(object, &m, &position, &eye, &normal, 0.0);
     assert_approx_eq!(result, Color::new_rgb(0.1, 0.1, 0.1));
 }
 
@@ -139,7 +148,7 @@ fn lighting_with_pattern() {
         &point(0.9, 0.0, 0.0),
         &eye,
         &normal,
-        false,
+        1.0,
     );
     let c2 = light.lighting(
         object.clone(),
@@ -147,12 +156,539 @@ fn lighting_with_pattern() {
         &point(1.1, 0.0, 0.0),
         &eye,
         &normal,
-        false,
+        1.0,
     );
     assert_approx_eq!(c1, Color::white());
     assert_approx_eq!(c2, Color::black());
 }
 
+#[test]
+fn lighting_uses_intensity() {
+    let mut m = Material::default();
+    m.ambient = 0.1;
+    m.diffuse = 0.9;
+    m.specular = 0.;
+    let light = PointLight::new(point(0., 0., -10.), Color::white());
+    let object = Arc::new(Shape::default());
+    let (position, eye, normal) = (point(0., 0., -1.), vector(0., 0., -1.), vector(0., 0., -1.));
+    for (intensity, expected) in [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)] {
+        let result = light.lighting(object.clone(), &m, &position, &eye, &normal, intensity);
+        assert_approx_eq!(result, Color::new_rgb(expected, expected, expected));
+    }
+}
+
+#[test]
+fn lighting_weights_samples_by_their_transmittance() {
+    let mut m = Material::default();
+    m.ambient = 0.;
+    m.diffuse = 0.9;
+    m.specular = 0.;
+    let light = PointLight::default();
+    let (position, eye, normal) = (point(0., 0., -1.), vector(0., 0., -1.), vector(0., 0., -1.));
+    let sample = |direction: Vec3D| LightSample {
+        direction: direction.unit(),
+        distance: 10.,
+        intensity: Color::white(),
+    };
+    let head_on = sample(vector(0., 0., -1.));
+    let grazing = sample(vector(0., 1., -1.));
+    for (transmittances, expected) in [
+        ((Color::white(), Color::black()), 0.45),
+        (
+            (Color::black(), Color::white()),
+            0.9 * consts::FRAC_1_SQRT_2 / 2.,
+        ),
+        (
+            (Color::white() * 0.5, Color::white()),
+            0.45 / 2. + 0.9 * consts::FRAC_1_SQRT_2 / 2.,
+        ),
+    ] {
+        let samples = [
+            (head_on.clone(), transmittances.0),
+            (grazing.clone(), transmittances.1),
+        ];
+        let result = light.lighting_with_samples(m.color, &m, &position, &eye, &normal, &samples);
+        let expected = expected as f32;
+        assert_approx_eq!(result, Color::new_rgb(expected, expected, expected));
+    }
+}
+
+fn rectangle_light() -> RectangleLight {
+    let mut light = RectangleLight::new(
+        point(-0.5, -0.5, -5.),
+        vector(1., 0., 0.),
+        2,
+        vector(0., 1., 0.),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    light
+}
+
+/// Positions of the samples of `light` as seen from `point`
+fn sample_positions(light: &impl Light, point: &Point) -> Vec<Point> {
+    light
+        .samples(point)
+        .iter()
+        .map(|sample| point + &sample.direction * sample.distance)
+        .collect()
+}
+
+#[test]
+fn rectangle_light_samples() {
+    let mut light = RectangleLight::new(
+        Point::origin(),
+        vector(2., 0., 0.),
+        4,
+        vector(0., 0., 1.),
+        2,
+        Color::white(),
+    );
+    light.jitter = false;
+    let samples = sample_positions(&light, &point(0., 5., 0.));
+    assert_eq!(samples.len(), 8);
+    for (i, expected) in [
+        (0, point(0.25, 0., 0.25)),
+        (1, point(0.75, 0., 0.25)),
+        (2, point(1.25, 0., 0.25)),
+        (4, point(0.25, 0., 0.75)),
+        (7, point(1.75, 0., 0.75)),
+    ] {
+        assert_approx_eq!(samples[i], &expected);
+    }
+
+    light.jitter = true;
+    let p = point(1., 2., 3.);
+    let jittered = sample_positions(&light, &p);
+    // the samples are reproducible and stay within their cells
+    assert_eq!(light.samples(&p), light.samples(&p));
+    for (sample, center) in jittered.iter().zip(samples.iter()) {
+        assert!((sample.x() - center.x()).abs() <= 0.25);
+        assert!((sample.z() - center.z()).abs() <= 0.25);
+    }
+}
+
+// expected values rounded to a few digits
+#[allow(clippy::approx_constant)]
+#[test]
+fn lighting_samples_area_light() {
+    let light = rectangle_light();
+    let mut m = Material::default();
+    m.ambient = 0.1;
+    m.diffuse = 0.9;
+    m.specular = 0.;
+    let object = Arc::new(Shape::default());
+    let eye = point(0., 0., -5.);
+    for (position, expected) in [
+        (point(0., 0., -1.), 0.9965),
+        (point(0., 0.7071, -0.7071), 0.62318),
+    ] {
+        let eye_v = (&eye - &position).unit();
+        let normal = &position - &Point::origin();
+        let result = light.lighting(object.clone(), &m, &position, &eye_v, &normal, 1.0);
+        assert_approx_eq!(result, Color::new_rgb(expected, expected, expected));
+    }
+}
+
+#[test]
+fn sphere_light_samples() {
+    let light = SphereLight::new(point(0., 10., 0.), 2., 4, Color::white());
+    let p = point(3., 0., 4.);
+    let samples = sample_positions(&light, &p);
+    assert_eq!(samples.len(), 16);
+    assert_eq!(light.samples(&p), light.samples(&p));
+    let to_point = &p - &light.center;
+    for sample in samples.iter() {
+        let offset = sample - &light.center;
+        assert!(offset.clone().mag() <= 2. + 1e-9);
+        // on the disk facing the lit point
+        assert!((&offset).scalar_prod(&to_point).abs() < 1e-9);
+    }
+}
+
+#[test]
+fn spot_light_cone() {
+    let light = SpotLight::new(
+        point(0., 10., 0.),
+        vector(0., -1., 0.),
+        consts::FRAC_PI_4,
+        1.,
+        Color::white(),
+    );
+    for (p, expected) in [
+        (point(0., 0., 0.), 1.),
+        (point(5., 0., 0.), 0.63955),
+        (point(10., 0., 0.), 0.),
+        (point(20., 0., 0.), 0.),
+        (point(0., 20., 0.), 0.),
+    ] {
+        let samples = light.samples(&p);
+        assert_eq!(samples.len(), 1);
+        assert_approx_eq!(samples[0].intensity, Color::white() * expected);
+    }
+
+    // without falloff the whole cone is lit evenly
+    let mut light = light;
+    light.falloff = 0.;
+    assert_approx_eq!(
+        light.samples(&point(9., 0., 0.))[0].intensity,
+        Color::white()
+    );
+}
+
+#[test]
+fn lighting_spot_light() {
+    let light = SpotLight::new(
+        point(0., 0., -10.),
+        vector(0., 0., 1.),
+        0.1,
+        0.,
+        Color::white(),
+    );
+    let object = Arc::new(Shape::default());
+    let m = Material::default();
+    let eye_v = vector(0., 0., -1.);
+    let normal = vector(0., 0., -1.);
+    let result = light.lighting(object.clone(), &m, &Point::origin(), &eye_v, &normal, 1.0);
+    assert_approx_eq!(result, Color::new_rgb(1.9, 1.9, 1.9));
+    // outside of the cone only ambient light remains
+    let result = light.lighting(object, &m, &point(0., 5., 0.), &eye_v, &normal, 1.0);
+    assert_approx_eq!(result, Color::new_rgb(0.1, 0.1, 0.1));
+}
+
+#[test]
+fn directional_light_samples() {
+    let light = DirectionalLight::new(vector(0., -2., 0.), Color::white());
+    for p in [Point::origin(), point(100., -50., 3.)] {
+        let samples = light.samples(&p);
+        assert_eq!(samples.len(), 1);
+        assert_approx_eq!(samples[0].direction, &vector(0., 1., 0.));
+        assert_eq!(samples[0].distance, f64::INFINITY);
+    }
+}
+
+#[test]
+fn lighting_directional_light() {
+    let light = DirectionalLight::new(vector(0., -1., 1.), Color::white());
+    let m = Material::default();
+    let result = light.lighting(
+        Arc::new(Shape::default()),
+        &m,
+        &Point::origin(),
+        &vector(0., 0., -1.),
+        &vector(0., 1., 0.),
+        1.0,
+    );
+    let diffuse = 0.9 * consts::FRAC_1_SQRT_2 as f32;
+    assert_approx_eq!(
+        result,
+        Color::new_rgb(0.1 + diffuse, 0.1 + diffuse, 0.1 + diffuse)
+    );
+}
+
+#[test]
+fn attenuation() {
+    for (attenuation, expected) in [
+        (Attenuation::None, [1., 1., 1.]),
+        (Attenuation::Linear, [1., 0.5, 0.25]),
+        (Attenuation::InverseSquare, [1., 0.25, 0.0625]),
+    ] {
+        for (distance, expected) in [1., 2., 4.].iter().zip(expected) {
+            assert_approx_eq!(attenuation.factor(*distance), expected);
+        }
+    }
+}
+
+#[test]
+fn point_light_with_power() {
+    let power = 4. * consts::PI * 100.;
+    let light = PointLight::with_power(Point::origin(), Color::white(), power);
+    assert_eq!(light.attenuation, Attenuation::InverseSquare);
+    for (p, expected) in [
+        (point(0., 1., 0.), 100.),
+        (point(0., 0., -10.), 1.),
+        (point(3., 4., 0.), 4.),
+    ] {
+        let sample = &light.samples(&p)[0];
+        assert_approx_eq!(sample.intensity, Color::white() * expected);
+        assert_approx_eq!(light.ambient(&p), Color::white() * expected);
+    }
+}
+
+#[test]
+fn lighting_attenuated() {
+    let mut light = PointLight::new(point(0., 0., -2.), Color::white() * 4.);
+    light.attenuation = Attenuation::InverseSquare;
+    let result = light.lighting(
+        Arc::new(Shape::default()),
+        &Material::default(),
+        &Point::origin(),
+        &vector(0., 0., -1.),
+        &vector(0., 0., -1.),
+        1.0,
+    );
+    // at distance 2 the light has intensity 1, like the unattenuated light of `lighting_eye_between`
+    assert_approx_eq!(result, Color::new_rgb(1.9, 1.9, 1.9));
+}
+
+#[test]
+fn attenuation_at_the_light() {
+    for attenuation in [Attenuation::Linear, Attenuation::InverseSquare] {
+        let factor = attenuation.factor(0.);
+        assert!(factor.is_finite());
+        assert_eq!(factor, attenuation.factor(EPSILON_F64));
+        assert_eq!(factor, attenuation.factor(-1.));
+    }
+    assert_approx_eq!(Attenuation::InverseSquare.factor(0.5), 4.);
+}
+
+#[test]
+fn spot_light_with_power() {
+    let cone_angle = consts::FRAC_PI_3;
+    // the cone covers a solid angle of π
+    let light = SpotLight::with_power(
+        Point::origin(),
+        vector(0., 0., 1.),
+        cone_angle,
+        0.,
+        Color::white(),
+        consts::PI,
+    );
+    assert_approx_eq!(
+        light.samples(&point(0., 0., 2.))[0].intensity,
+        Color::white() * 0.25
+    );
+}
+
+#[test]
+fn beer_lambert_absorption() {
+    let absorption = Color::new_rgb(1., 0., 2.);
+    assert_approx_eq!(
+        beer_lambert(absorption, 1.),
+        Color::new_rgb(0.36788, 1., 0.13534)
+    );
+    assert_approx_eq!(beer_lambert(absorption, 0.), Color::white());
+    // light that never hits anything is absorbed completely, unless nothing is absorbed
+    assert_approx_eq!(
+        beer_lambert(absorption, f64::INFINITY),
+        Color::new_rgb(0., 1., 0.)
+    );
+    assert_approx_eq!(beer_lambert(Color::black(), f64::INFINITY), Color::white());
+}
+
+#[test]
+fn default_model_is_phong() {
+    assert_eq!(Material::default().model, ShadingModel::Phong);
+    let m = Material::new_pbr(Color::white(), 2., -1.);
+    assert_eq!(
+        m.model,
+        ShadingModel::CookTorrance {
+            metallic: 1.,
+            roughness: 0.
+        }
+    );
+}
+
+#[test]
+fn lighting_cook_torrance_head_on() {
+    let light = PointLight::new(point(0., 0., -10.), Color::white());
+    let object = Arc::new(Shape::default());
+    let eye_v = vector(0., 0., -1.);
+    let normal = vector(0., 0., -1.);
+    for (metallic, expected) in [
+        // ambient, diffuse scattering of the 96% that enter the surface and 4% reflection
+        (0., 0.1 + 0.96 + 0.01),
+        // metals don't scatter diffusely but reflect all of the light
+        (1., 0.1 + 0.25),
+    ] {
+        let m = Material::new_pbr(Color::white(), metallic, 1.);
+        let result = light.lighting(object.clone(), &m, &Point::origin(), &eye_v, &normal, 1.0);
+        assert_approx_eq!(result, Color::new_rgb(expected, expected, expected));
+    }
+}
+
+#[test]
+fn cook_torrance_as_bright_as_phong() {
+    // light at 60° to the normal, far from the Phong highlight
+    let light = PointLight::new(point(0., 10., -10. / 3f64.sqrt()), Color::white());
+    let object = Arc::new(Shape::default());
+    let eye_v = vector(0., 0., -1.);
+    let normal = vector(0., 0., -1.);
+    let lit = |m: &Material| {
+        light
+            .lighting(object.clone(), m, &Point::origin(), &eye_v, &normal, 1.0)
+            .r
+    };
+    let phong = lit(&Material::default());
+    assert_approx_eq!(phong, 0.1 + 0.9 * 0.5);
+    let pbr = lit(&Material::new_pbr(Color::white(), 0., 1.));
+    assert!((pbr - phong).abs() < 0.1 * phong);
+}
+
+#[test]
+fn lighting_cook_torrance_light_behind_surface() {
+    let light = PointLight::new(point(0., 0., 10.), Color::white());
+    let m = Material::new_pbr(Color::white(), 0.5, 0.5);
+    let result = light.lighting(
+        Arc::new(Shape::default()),
+        &m,
+        &Point::origin(),
+        &vector(0., 0., -1.),
+        &vector(0., 0., -1.),
+        1.0,
+    );
+    assert_approx_eq!(result, Color::new_rgb(0.1, 0.1, 0.1));
+}
+
+#[test]
+fn cook_torrance_highlights() {
+    let normal = vector(0., 1., 0.);
+    let light_v = vector(-1., 1., 0.).unit();
+    let mirror = vector(1., 1., 0.).unit();
+    let off_mirror = vector(0.2, 1., 0.).unit();
+    let specular = |roughness: f32, eye: &Vec3D| {
+        cook_torrance(Color::white(), 1., roughness, &normal, eye, &light_v).r
+    };
+    // smooth surfaces concentrate the reflection around the mirror direction
+    assert!(specular(0.2, &mirror) > specular(0.8, &mirror));
+    assert!(specular(0.2, &off_mirror) < specular(0.8, &off_mirror));
+    assert!(specular(0.2, &mirror) > 10. * specular(0.2, &off_mirror));
+    // metals tint their reflection with their color
+    let gold = Color::new_rgb(1., 0.78, 0.34);
+    let reflected = cook_torrance(gold, 1., 0.5, &normal, &light_v, &light_v);
+    assert!(reflected.r > reflected.g && reflected.g > reflected.b);
+}
+
+#[test]
+fn mesh_light_samples() {
+    let glowing = Material::new_emissive(Color::white(), 2.);
+    let shape = Shape::new_rectangle(
+        2.,
+        4.,
+        glowing.clone(),
+        Transformation::new_translation(0., 5., 0.),
+    );
+    let light = MeshLight::new(&shape, 16).unwrap();
+    assert_approx_eq!(light.area(), 8.);
+    assert_approx_eq!(light.intensity(), Color::white() * 2.);
+    assert!(light.is_emissive_shape());
+    let p = point(1., 0., 2.);
+    let samples = sample_positions(&light, &p);
+    assert_eq!(samples.len(), 16);
+    assert_eq!(light.samples(&p), light.samples(&p));
+    for sample in samples.iter() {
+        assert_approx_eq!(sample.y(), 5.);
+        assert!(sample.x().abs() <= 1. + 1e-9 && sample.z().abs() <= 2. + 1e-9);
+    }
+
+    // the triangles of a group are sampled in proportion to their area
+    let group = Shape::new_group(
+        vec![
+            Shape::new_triangle(
+                Point::origin(),
+                point(1., 0., 0.),
+                point(0., 0., 1.),
+                glowing.clone(),
+                Transformation::identity(),
+            ),
+            Shape::new_triangle(
+                Point::origin(),
+                point(3., 0., 0.),
+                point(0., 0., 3.),
+                Material::default(),
+                Transformation::identity(),
+            ),
+        ],
+        Transformation::new_translation(0., 1., 0.),
+    );
+    let light = MeshLight::new(&group, 1).unwrap();
+    assert_approx_eq!(light.area(), 5.);
+    assert_approx_eq!(light.intensity(), Color::white() * 0.2);
+}
+
+#[test]
+fn mesh_light_unsupported_shapes() {
+    let glowing = Material::new_emissive(Color::white(), 2.);
+    let sphere = Shape::new_sphere(glowing.clone(), Transformation::identity());
+    assert_eq!(
+        MeshLight::new(&sphere, 4).unwrap_err(),
+        MeshLightError::NothingToSample
+    );
+    // within groups only the supported shapes are sampled
+    let group = Shape::new_group(
+        vec![
+            sphere,
+            Shape::new_rectangle(2., 2., glowing, Transformation::identity()),
+        ],
+        Transformation::identity(),
+    );
+    assert_approx_eq!(MeshLight::new(&group, 4).unwrap().area(), 4.);
+}
+
+#[test]
+fn mesh_light_without_samples() {
+    let glowing = Material::new_emissive(Color::white(), 2.);
+    let rectangle = Shape::new_rectangle(2., 2., glowing, Transformation::identity());
+    assert_eq!(
+        MeshLight::new(&rectangle, 0).unwrap_err(),
+        MeshLightError::NoSamples
+    );
+}
+
+#[test]
+fn mesh_light_skips_degenerate_triangles() {
+    let glowing = Material::new_emissive(Color::white(), 2.);
+    let degenerate = || {
+        Shape::new_triangle(
+            point(0., 0., 0.),
+            point(1., 0., 0.),
+            point(2., 0., 0.),
+            glowing.clone(),
+            Transformation::identity(),
+        )
+    };
+    assert_eq!(
+        MeshLight::new(&degenerate(), 4).unwrap_err(),
+        MeshLightError::NothingToSample
+    );
+    let group = Shape::new_group(
+        vec![
+            degenerate(),
+            Shape::new_rectangle(2., 2., glowing.clone(), Transformation::identity()),
+        ],
+        Transformation::identity(),
+    );
+    let light = MeshLight::new(&group, 4).unwrap();
+    assert_approx_eq!(light.area(), 4.);
+    assert_approx_eq!(light.intensity(), Color::white() * 2.);
+    let p = point(0., 3., 0.);
+    for sample in light.samples(&p) {
+        assert!(!sample.intensity.r.is_nan());
+    }
+}
+
+#[test]
+fn mesh_light_intensity_falls_off() {
+    let shape = Shape::new_rectangle(
+        0.1,
+        0.1,
+        Material::new_emissive(Color::white(), 1.),
+        Transformation::new_x_rotation(consts::FRAC_PI_2),
+    );
+    let mut light = MeshLight::new(&shape, 1).unwrap();
+    light.jitter = false;
+    // seen from far away the surface acts like a point light
+    for (p, expected) in [
+        (point(0., 0., -10.), 0.01 / (consts::PI * 100.)),
+        (point(0., 0., 20.), 0.01 / (consts::PI * 400.)),
+        (point(20., 0., 0.), 0.),
+    ] {
+        let intensity = light.samples(&p)[0].intensity;
+        assert!((intensity.r as f64 - expected).abs() < expected * 0.01 + 1e-9);
+    }
+}
+
 #[test]
 fn stripes_with_object_transformation() {
     let mut object = Shape::default();
@@ -258,3 +794,123 @@ fn checkers() {
         Color::black()
     );
 }
+
+/// A 2 by 2 image, black and white in the top row and red and blue in the bottom row
+fn texture_image() -> Canvas {
+    let mut image = Canvas::new(2, 2);
+    image[(0, 0)] = Color::black();
+    image[(0, 1)] = Color::white();
+    image[(1, 0)] = Color::new_rgb(1., 0., 0.);
+    image[(1, 1)] = Color::new_rgb(0., 0., 1.);
+    image
+}
+
+#[test]
+fn texture_mappings() {
+    let cases = vec![
+        (UvMapping::Spherical, point(0., 0., -1.), (0., 0.5)),
+        (UvMapping::Spherical, point(1., 0., 0.), (0.25, 0.5)),
+        (UvMapping::Spherical, point(0., 0., 1.), (0.5, 0.5)),
+        (UvMapping::Spherical, point(-1., 0., 0.), (0.75, 0.5)),
+        (UvMapping::Spherical, point(0., 1., 0.), (0.5, 1.)),
+        (UvMapping::Spherical, point(0., -1., 0.), (0.5, 0.)),
+        (
+            UvMapping::Spherical,
+            point(consts::SQRT_2 / 2., consts::SQRT_2 / 2., 0.),
+            (0.25, 0.75),
+        ),
+        (UvMapping::Planar, point(0.25, 0.5, 0.75), (0.25, 0.75)),
+        (UvMapping::Planar, point(-1.25, 0., 2.5), (-1.25, 2.5)),
+        (UvMapping::Cylindrical, point(0., 0.5, -1.), (0., 0.5)),
+        (UvMapping::Cylindrical, point(1., 1.25, 0.), (0.25, 1.25)),
+        (UvMapping::Cubic, point(-1., 0.5, -0.25), (0.375, 0.75)),
+        (UvMapping::Cubic, point(0.5, 0.5, 1.), (0.75, 0.75)),
+        (UvMapping::Cubic, point(0.25, -1., 0.5), (0.625, 0.75)),
+        (UvMapping::Mesh, point(1., 2., 3.), (0.2, 0.4)),
+    ];
+    for (mapping, p, (u, v)) in cases {
+        let texture = ImageTexture::new(texture_image(), mapping);
+        let uv = texture.uv_at(&p, (0.2, 0.4));
+        assert_approx_eq!(uv.0, u);
+        assert_approx_eq!(uv.1, v);
+    }
+}
+
+#[test]
+fn texture_filtering() {
+    let mut texture = ImageTexture::new(texture_image(), UvMapping::Planar);
+    texture.filter = Filter::Nearest;
+    assert_approx_eq!(texture.color_at_uv(0.25, 0.75), Color::black());
+    assert_approx_eq!(texture.color_at_uv(0.7, 0.9), Color::white());
+    assert_approx_eq!(texture.color_at_uv(0.1, 0.4), Color::new_rgb(1., 0., 0.));
+    assert_approx_eq!(texture.color_at_uv(0.6, 0.1), Color::new_rgb(0., 0., 1.));
+
+    texture.filter = Filter::Bilinear;
+    // pixel centers keep their color, between them colors are blended
+    assert_approx_eq!(texture.color_at_uv(0.25, 0.75), Color::black());
+    assert_approx_eq!(texture.color_at_uv(0.5, 0.75), Color::white() * 0.5);
+    assert_approx_eq!(texture.color_at_uv(0.25, 0.5), Color::new_rgb(0.5, 0., 0.));
+    assert_approx_eq!(
+        texture.color_at_uv(0.5, 0.5),
+        Color::new_rgb(0.5, 0.25, 0.5)
+    );
+}
+
+#[test]
+fn texture_wrap_modes() {
+    let mut texture = ImageTexture::new(texture_image(), UvMapping::Planar);
+    texture.filter = Filter::Nearest;
+    assert_eq!(texture.wrap, Wrap::Repeat);
+    assert_approx_eq!(texture.color_at_uv(1.25, 0.75), Color::black());
+    assert_approx_eq!(
+        texture.color_at_uv(-0.25, -0.75),
+        Color::new_rgb(0., 0., 1.)
+    );
+    texture.filter = Filter::Bilinear;
+    assert_approx_eq!(texture.color_at_uv(0., 0.75), Color::white() * 0.5);
+
+    texture.wrap = Wrap::Clamp;
+    assert_approx_eq!(texture.color_at_uv(0., 0.75), Color::black());
+    texture.filter = Filter::Nearest;
+    assert_approx_eq!(texture.color_at_uv(1.25, 0.75), Color::white());
+    assert_approx_eq!(
+        texture.color_at_uv(-0.25, -0.75),
+        Color::new_rgb(1., 0., 0.)
+    );
+}
+
+#[test]
+fn texture_pattern() {
+    let mut texture = ImageTexture::new(texture_image(), UvMapping::Planar);
+    texture.filter = Filter::Nearest;
+    // the image covers the square from 0 to 2
+    let pattern = Pattern::new_texture(texture, Transformation::new_scaling(2., 2., 2.));
+    assert!(pattern.texture().is_some());
+    assert_approx_eq!(
+        pattern.at(Arc::new(Shape::default()), &point(1.5, 0., 1.5)),
+        Color::white()
+    );
+    assert_approx_eq!(
+        pattern.at_object_point(&point(0.5, 0., 0.5)),
+        Color::new_rgb(1., 0., 0.)
+    );
+
+    let mut texture = ImageTexture::new(texture_image(), UvMapping::Mesh);
+    texture.filter = Filter::Nearest;
+    let pattern = Pattern::new_texture(texture, Transformation::identity());
+    assert_approx_eq!(
+        pattern.at_surface_point(&Point::origin(), (0.9, 0.1)),
+        Color::new_rgb(0., 0., 1.)
+    );
+    assert!(Pattern::new(TEST_PATTERN, Transformation::identity())
+        .texture()
+        .is_none());
+}
+
+#[test]
+fn texture_from_missing_file() {
+    assert!(matches!(
+        ImageTexture::from_file("does/not/exist.ppm", UvMapping::Spherical),
+        Err(crate::parsers::NetpbmError::Io(_))
+    ));
+}
@@ -0,0 +1,186 @@
+use std::{f64::consts::PI, fmt, path::Path, sync::Arc};
+
+use crate::{
+    parsers::{read_netpbm, NetpbmError},
+    primitives::{canvas::Canvas, vector::Point},
+};
+
+use super::Color;
+
+/// How points in pattern space are projected onto the surface coordinates (u, v) of a texture.
+/// u runs to the right and v upwards in the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMapping {
+    /// Project along the y axis, x becomes u and z becomes v. The image covers the unit square.
+    Planar,
+    /// Wrap the image around the unit sphere, like a map of the earth. u runs around the y axis
+    /// and v from the south to the north pole.
+    Spherical,
+    /// Wrap the image once around the y axis, it repeats every unit along it
+    Cylindrical,
+    /// Project onto the faces of the cube from -1 to 1, every face shows the whole image
+    Cubic,
+    /// Use the surface coordinates of the hit, e.g. the texture coordinates of triangles read
+    /// from OBJ files
+    Mesh,
+}
+
+/// How a texture is sampled between the centers of its pixels
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Filter {
+    /// Take the color of the closest pixel, this keeps hard edges of pixel art
+    Nearest,
+    /// Blend the four closest pixels
+    #[default]
+    Bilinear,
+}
+
+/// What a texture looks like outside of the range from 0 to 1 of its surface coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Wrap {
+    /// Tile the image
+    #[default]
+    Repeat,
+    /// Continue the edge pixels of the image
+    Clamp,
+}
+
+/// An image mapped onto a surface
+#[derive(Clone)]
+pub struct ImageTexture {
+    image: Arc<Canvas>,
+    pub mapping: UvMapping,
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+impl fmt::Debug for ImageTexture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ImageTexture {{ width: {}, height: {}, mapping: {:?}, filter: {:?}, wrap: {:?} }}",
+            self.image.width(),
+            self.image.height(),
+            self.mapping,
+            self.filter,
+            self.wrap
+        )
+    }
+}
+
+impl ImageTexture {
+    pub fn new(image: Canvas, mapping: UvMapping) -> Self {
+        assert!(
+            image.width() > 0 && image.height() > 0,
+            "Textures need at least one pixel."
+        );
+        ImageTexture {
+            image: Arc::new(image),
+            mapping,
+            filter: Filter::default(),
+            wrap: Wrap::default(),
+        }
+    }
+
+    /// Load the image of a texture from a PPM, PGM or PFM file
+    pub fn from_file(path: impl AsRef<Path>, mapping: UvMapping) -> Result<Self, NetpbmError> {
+        Ok(Self::new(read_netpbm(path)?, mapping))
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    /// The surface coordinates of a point in pattern space. `surface_uv` are the coordinates of
+    /// the hit, only used by the mesh mapping.
+    pub fn uv_at(&self, point: &Point, surface_uv: (f64, f64)) -> (f64, f64) {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        match self.mapping {
+            UvMapping::Planar => (x, z),
+            UvMapping::Spherical => {
+                let radius = (x * x + y * y + z * z).sqrt();
+                let phi = (y / radius).clamp(-1., 1.).acos();
+                (around_y_axis(x, z), 1. - phi / PI)
+            }
+            UvMapping::Cylindrical => (around_y_axis(x, z), y),
+            UvMapping::Cubic => {
+                let (_, u, v) = cube_face_uv(x, y, z);
+                (u, v)
+            }
+            UvMapping::Mesh => surface_uv,
+        }
+    }
+
+    /// The color of the texture at some point in pattern space
+    pub fn at(&self, point: &Point, surface_uv: (f64, f64)) -> Color {
+        let (u, v) = self.uv_at(point, surface_uv);
+        self.color_at_uv(u, v)
+    }
+
+    /// The color of the image at the surface coordinates (u, v). (0, 0) is the bottom left
+    /// corner of the image and (1, 1) the top right one.
+    pub fn color_at_uv(&self, u: f64, v: f64) -> Color {
+        // position in pixels from the top left corner
+        let x = u * self.image.width() as f64;
+        let y = (1. - v) * self.image.height() as f64;
+        match self.filter {
+            Filter::Nearest => self.pixel(x.floor() as isize, y.floor() as isize),
+            Filter::Bilinear => {
+                // the centers of the pixels lie halfway between the integers
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (column, row) = (x.floor() as isize, y.floor() as isize);
+                let (s, t) = ((x - x.floor()) as f32, (y - y.floor()) as f32);
+                let top = self.pixel(column, row) * (1. - s) + self.pixel(column + 1, row) * s;
+                let bottom =
+                    self.pixel(column, row + 1) * (1. - s) + self.pixel(column + 1, row + 1) * s;
+                top * (1. - t) + bottom * t
+            }
+        }
+    }
+
+    /// The pixel at some column and row, which may lie outside of the image
+    fn pixel(&self, column: isize, row: isize) -> Color {
+        let wrap = |i: isize, size: usize| match self.wrap {
+            Wrap::Repeat => i.rem_euclid(size as isize) as usize,
+            Wrap::Clamp => i.clamp(0, size as isize - 1) as usize,
+        };
+        self.image[(
+            wrap(row, self.image.height()),
+            wrap(column, self.image.width()),
+        )]
+    }
+}
+
+/// The angle around the y axis as a fraction of a full turn. It starts at the negative z axis
+/// and increases towards the positive x axis, as seen from outside.
+fn around_y_axis(x: f64, z: f64) -> f64 {
+    0.5 - x.atan2(z) / (2. * PI)
+}
+
+/// The face of the cube from -1 to 1 that the direction (x, y, z) points at, together with the
+/// position on the face from 0 to 1. The faces are numbered left (-x), front (+z), right (+x),
+/// back (-z), up (+y) and down (-y). u runs to the right and v upwards as seen from inside the
+/// cube, the top of the up face lies at the back and that of the down face at the front.
+pub fn cube_face_uv(x: f64, y: f64, z: f64) -> (usize, f64, f64) {
+    let coord = x.abs().max(y.abs()).max(z.abs());
+    // the point where the direction leaves the cube
+    let (px, py, pz) = (x / coord, y / coord, z / coord);
+    let (face, u, v) = if coord == x.abs() {
+        if x < 0. {
+            (0, pz + 1., py + 1.)
+        } else {
+            (2, 1. - pz, py + 1.)
+        }
+    } else if coord == y.abs() {
+        if y > 0. {
+            (4, px + 1., 1. - pz)
+        } else {
+            (5, px + 1., pz + 1.)
+        }
+    } else if z > 0. {
+        (1, px + 1., py + 1.)
+    } else {
+        (3, 1. - px, py + 1.)
+    };
+    (face, (u / 2.).clamp(0., 1.), (v / 2.).clamp(0., 1.))
+}
@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use super::{prelude::*, triangle::intersect_triangle};
+
+use crate::{
+    primitives::{
+        ray::Ray,
+        vector::{CrossProd, Point, ScalarProd, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// Newton iterations used to find the exact intersection with a patch
+const NEWTON_ITERATIONS: usize = 12;
+/// Intersections with a distance smaller than this to the patch are accepted
+const NEWTON_TOLERANCE: f64 = 1e-9;
+/// Upper limit for the number of times the parameter range is halved when tessellating
+const MAX_BISECTIONS: usize = 6;
+
+/// How a Bézier patch is turned into something that can be intersected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatchIntersection {
+    /// Split the patch into smooth triangles, subdividing until no point of the surface is further
+    /// than `tolerance` (in object space) from the triangles
+    Tessellated { tolerance: f64 },
+    /// Intersect the exact surface using Newton iteration. The initial guesses come from a coarse
+    /// tessellation with `resolution` subdivisions per direction.
+    Newton { resolution: usize },
+}
+
+impl Default for PatchIntersection {
+    fn default() -> Self {
+        PatchIntersection::Tessellated { tolerance: 1e-3 }
+    }
+}
+
+/// Cubic Bernstein polynomials
+fn bernstein(t: f64) -> [f64; 4] {
+    let s = 1. - t;
+    [s * s * s, 3. * t * s * s, 3. * t * t * s, t * t * t]
+}
+
+/// Derivatives of the cubic Bernstein polynomials
+fn bernstein_derivative(t: f64) -> [f64; 4] {
+    let s = 1. - t;
+    [
+        -3. * s * s,
+        3. * s * s - 6. * t * s,
+        6. * t * s - 3. * t * t,
+        3. * t * t,
+    ]
+}
+
+/// Second derivatives of the cubic Bernstein polynomials
+fn bernstein_second_derivative(t: f64) -> [f64; 4] {
+    let s = 1. - t;
+    [6. * s, 6. * t - 12. * s, 6. * s - 12. * t, 6. * t]
+}
+
+/// Split the interval from `lo` to `hi` in halves until `flat` holds for every part or the
+/// maximum depth is reached, and append the upper ends of the parts to `params`
+fn bisect(lo: f64, hi: f64, depth: usize, flat: &dyn Fn(f64, f64) -> bool, params: &mut Vec<f64>) {
+    if depth < MAX_BISECTIONS && !flat(lo, hi) {
+        let mid = (lo + hi) / 2.;
+        bisect(lo, mid, depth + 1, flat, params);
+        bisect(mid, hi, depth + 1, flat, params);
+    } else {
+        params.push(hi);
+    }
+}
+
+/// A bicubic Bézier patch given by a 4x4 grid of control points. Rows of the grid run along u and
+/// columns along v.
+#[derive(Clone, Debug)]
+pub struct BezierPatch {
+    pub control_points: [Point; 16],
+    /// Surface points sampled on a regular grid, used as starting points for Newton iteration
+    coarse_grid: Vec<Point>,
+    resolution: usize,
+}
+
+impl BezierPatch {
+    pub fn new(control_points: [Point; 16]) -> Self {
+        let mut patch = BezierPatch {
+            control_points,
+            coarse_grid: vec![],
+            resolution: 0,
+        };
+        patch.set_resolution(4);
+        patch
+    }
+
+    /// Set the resolution of the tessellation used to find starting points for Newton iteration
+    pub fn set_resolution(&mut self, resolution: usize) {
+        let resolution = resolution.max(1);
+        self.resolution = resolution;
+        self.coarse_grid = self.grid(resolution, resolution);
+    }
+
+    fn combine(&self, bu: [f64; 4], bv: [f64; 4]) -> Point {
+        let mut p = Point::origin() * 0.;
+        for (row, wu) in self.control_points.chunks(4).zip(bu.iter()) {
+            for (cp, wv) in row.iter().zip(bv.iter()) {
+                p += cp * (wu * wv);
+            }
+        }
+        p
+    }
+
+    pub fn point_at(&self, u: f64, v: f64) -> Point {
+        self.combine(bernstein(u), bernstein(v))
+    }
+
+    /// Partial derivatives of the surface with respect to u and v
+    pub fn tangents(&self, u: f64, v: f64) -> (Vec3D, Vec3D) {
+        (
+            self.combine(bernstein_derivative(u), bernstein(v)),
+            self.combine(bernstein(u), bernstein_derivative(v)),
+        )
+    }
+
+    /// Surface normal at (u, v). Where the patch degenerates (like at the tip of the teapot's
+    /// lid) the normal of a point slightly towards the center of the patch is used.
+    pub fn normal_at(&self, u: f64, v: f64) -> Vec3D {
+        let (mut u, mut v) = (u, v);
+        for _ in 0..8 {
+            let (du, dv) = self.tangents(u, v);
+            let n = (&du).cross(&dv);
+            if n.clone().mag() > 1e-12 {
+                return n.unit();
+            }
+            u += (0.5 - u) * 1e-3;
+            v += (0.5 - v) * 1e-3;
+        }
+        let (du, dv) = self.tangents(u, v);
+        (&du).cross(&dv)
+    }
+
+    /// Surface points on a regular grid with `nu + 1` by `nv + 1` points, row major along v
+    fn grid(&self, nu: usize, nv: usize) -> Vec<Point> {
+        (0..=nu)
+            .flat_map(|i| (0..=nv).map(move |j| (i, j)))
+            .map(|(i, j)| self.point_at(i as f64 / nu as f64, j as f64 / nv as f64))
+            .collect()
+    }
+
+    /// Upper bound of the second derivative along u (or v if `along_v`) at the parameter `t`,
+    /// for any value of the other parameter. The derivative is a convex combination of the
+    /// derivatives of the columns (or rows) of control points.
+    fn curvature_bound(&self, t: f64, along_v: bool) -> f64 {
+        let weights = bernstein_second_derivative(t);
+        (0..4)
+            .map(|k| {
+                let mut d = Point::origin() * 0.;
+                for (l, w) in weights.iter().enumerate() {
+                    let (i, j) = if along_v { (k, l) } else { (l, k) };
+                    d += &self.control_points[4 * i + j] * *w;
+                }
+                d.mag()
+            })
+            .fold(0., f64::max)
+    }
+
+    /// Parameters along u and v at which to split the patch so that no point of the surface is
+    /// further than `tolerance` from the triangles. Intervals are halved until they are flat
+    /// enough, so curved regions get split finer than flat ones. A cubic deviates at most
+    /// h^2 / 8 * max|second derivative| from its chord over an interval of length h, and the
+    /// second derivative is linear so it takes its maximum at one of the ends.
+    pub fn subdivisions(&self, tolerance: f64) -> (Vec<f64>, Vec<f64>) {
+        let tolerance = tolerance.max(1e-12);
+        let split = |along_v: bool| {
+            let flat = |lo: f64, hi: f64| {
+                let bound = self
+                    .curvature_bound(lo, along_v)
+                    .max(self.curvature_bound(hi, along_v));
+                (hi - lo).powi(2) / 8. * bound <= tolerance
+            };
+            let mut params = vec![0.];
+            bisect(0., 1., 0, &flat, &mut params);
+            params
+        };
+        (split(false), split(true))
+    }
+
+    /// Split the patch into smooth triangles that deviate less than `tolerance` from it
+    pub fn tessellate(&self, tolerance: f64, material: &Material) -> Vec<Shape> {
+        let (us, vs) = self.subdivisions(tolerance);
+        let vertex =
+            |(i, j): (usize, usize)| (self.point_at(us[i], vs[j]), self.normal_at(us[i], vs[j]));
+        let mut triangles = vec![];
+        for i in 0..us.len() - 1 {
+            for j in 0..vs.len() - 1 {
+                let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                for [a, b, c] in [[0, 1, 2], [0, 2, 3]].iter() {
+                    let (p1, n1) = vertex(corners[*a]);
+                    let (p2, n2) = vertex(corners[*b]);
+                    let (p3, n3) = vertex(corners[*c]);
+                    // triangles next to degenerate edges of the patch collapse to lines
+                    if (&(&p2 - &p1)).cross(&(&p3 - &p1)).mag() < 1e-12 {
+                        continue;
+                    }
+                    triangles.push(Shape::new_smooth_triangle(
+                        [p1, p2, p3],
+                        [n1, n2, n3],
+                        material.clone(),
+                        Transformation::identity(),
+                    ));
+                }
+            }
+        }
+        triangles
+    }
+
+    /// Refine an approximate intersection (u, v, t) using Newton iteration on
+    /// P(u, v) - (origin + t * direction) = 0
+    fn newton(&self, ray: &Ray, (mut u, mut v, mut t): (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+        for _ in 0..NEWTON_ITERATIONS {
+            let f = &self.point_at(u, v) - &ray.position(t);
+            if (&f).scalar_prod(&f) < NEWTON_TOLERANCE * NEWTON_TOLERANCE {
+                let eps = 1e-6;
+                if (-eps..=1. + eps).contains(&u) && (-eps..=1. + eps).contains(&v) {
+                    return Some((u.clamp(0., 1.), v.clamp(0., 1.), t));
+                }
+                return None;
+            }
+            // Solve [du dv -d] * delta = -f with Cramer's rule
+            let (du, dv) = self.tangents(u, v);
+            let d = -ray.direction.clone();
+            let det = (&du).scalar_prod(&(&dv).cross(&d));
+            if det.abs() < 1e-14 {
+                return None;
+            }
+            let f = -f;
+            u += (&f).scalar_prod(&(&dv).cross(&d)) / det;
+            v += (&du).scalar_prod(&(&f).cross(&d)) / det;
+            t += (&du).scalar_prod(&(&dv).cross(&f)) / det;
+        }
+        None
+    }
+}
+
+impl Geometry for BezierPatch {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        if !self
+            .local_bounds()
+            .intersects(ray, -f64::INFINITY, f64::INFINITY)
+        {
+            return None;
+        }
+        let n = self.resolution;
+        let grid_point = |i: usize, j: usize| &self.coarse_grid[i * (n + 1) + j];
+        let grid_uv = |i: usize, j: usize| (i as f64 / n as f64, j as f64 / n as f64);
+        let mut hits = vec![];
+        for i in 0..n {
+            for j in 0..n {
+                let cells = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                for [a, b, c] in [[0, 1, 2], [0, 2, 3]].iter() {
+                    let (a, b, c) = (cells[*a], cells[*b], cells[*c]);
+                    let hit = intersect_triangle(
+                        ray,
+                        grid_point(a.0, a.1),
+                        grid_point(b.0, b.1),
+                        grid_point(c.0, c.1),
+                    );
+                    if let Some((t, bu, bv)) = hit {
+                        let (ua, va) = grid_uv(a.0, a.1);
+                        let (ub, vb) = grid_uv(b.0, b.1);
+                        let (uc, vc) = grid_uv(c.0, c.1);
+                        let w = 1. - bu - bv;
+                        let guess = (w * ua + bu * ub + bv * uc, w * va + bu * vb + bv * vc, t);
+                        if let Some(hit) = self.newton(ray, guess) {
+                            hits.push(hit);
+                        }
+                    }
+                }
+            }
+        }
+        // neighbouring starting points may converge to the same intersection
+        hits.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap());
+        hits.dedup_by(|a, b| (a.2 - b.2).abs() < 1e-7);
+        if hits.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                hits.into_iter()
+                    .map(|(u, v, t)| Intersection::new_with_uv(t, Arc::clone(shape), u, v))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Point, hit: &Intersection) -> Option<Vec3D> {
+        Some(self.normal_at(hit.u, hit.v))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        // the patch lies within the convex hull of its control points
+        BoundingBox::from_points(self.control_points.iter())
+    }
+}
+
+impl Shape {
+    /// A shape for a single Bézier patch. Tessellated patches become groups of smooth triangles.
+    pub fn new_bezier_patch(
+        mut patch: BezierPatch,
+        intersection: PatchIntersection,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        match intersection {
+            PatchIntersection::Tessellated { tolerance } => {
+                Self::new_group(patch.tessellate(tolerance, &material), transform)
+            }
+            PatchIntersection::Newton { resolution } => {
+                patch.set_resolution(resolution);
+                Self::new(patch, material, transform)
+            }
+        }
+    }
+}
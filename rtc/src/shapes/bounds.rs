@@ -0,0 +1,249 @@
+use crate::primitives::{
+    ray::Ray,
+    vector::{point, Point, Transformation},
+};
+
+/// An axis aligned bounding box
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        BoundingBox { min, max }
+    }
+
+    /// A box that doesn't contain anything. Adding points to it grows it accordingly.
+    pub fn empty() -> Self {
+        Self::new(
+            point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            point(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+        )
+    }
+
+    /// A box containing all of space
+    pub fn infinite() -> Self {
+        Self::new(
+            point(-f64::INFINITY, -f64::INFINITY, -f64::INFINITY),
+            point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    /// Smallest box containing all given points
+    pub fn from_points<'a>(points: impl IntoIterator<Item = &'a Point>) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |bounds, p| bounds.with_point(p))
+    }
+
+    pub fn is_finite(&self) -> bool {
+        self.min
+            .iter()
+            .chain(self.max.iter())
+            .all(|c| c.is_finite())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    /// Grow the box such that it contains p
+    pub fn with_point(self, p: &Point) -> Self {
+        Self::new(
+            point(
+                self.min.x().min(p.x()),
+                self.min.y().min(p.y()),
+                self.min.z().min(p.z()),
+            ),
+            point(
+                self.max.x().max(p.x()),
+                self.max.y().max(p.y()),
+                self.max.z().max(p.z()),
+            ),
+        )
+    }
+
+    /// Smallest box containing both boxes
+    pub fn merge(self, other: &Self) -> Self {
+        self.with_point(&other.min).with_point(&other.max)
+    }
+
+    pub fn contains_point(&self, p: &Point) -> bool {
+        (self.min.x() <= p.x() && p.x() <= self.max.x())
+            && (self.min.y() <= p.y() && p.y() <= self.max.y())
+            && (self.min.z() <= p.z() && p.z() <= self.max.z())
+    }
+
+    pub fn contains_box(&self, other: &Self) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn centroid(&self) -> Point {
+        point(
+            (self.min.x() + self.max.x()) / 2.,
+            (self.min.y() + self.max.y()) / 2.,
+            (self.min.z() + self.max.z()) / 2.,
+        )
+    }
+
+    /// Surface area of the box, used as a cost metric when building hierarchies
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            0.
+        } else {
+            let d = &self.max - &self.min;
+            2. * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+        }
+    }
+
+    /// Bounding box of this box after applying a transformation to it
+    pub fn transform(&self, transformation: &Transformation) -> Self {
+        if self.is_empty() {
+            return self.clone();
+        }
+        if !self.is_finite() {
+            // transforming infinite coordinates yields NaNs, just be conservative
+            return Self::infinite();
+        }
+        let (min, max) = (&self.min, &self.max);
+        let corners = [
+            point(min.x(), min.y(), min.z()),
+            point(min.x(), min.y(), max.z()),
+            point(min.x(), max.y(), min.z()),
+            point(min.x(), max.y(), max.z()),
+            point(max.x(), min.y(), min.z()),
+            point(max.x(), min.y(), max.z()),
+            point(max.x(), max.y(), min.z()),
+            point(max.x(), max.y(), max.z()),
+        ];
+        let transformed = corners
+            .iter()
+            .map(|corner| transformation * corner)
+            .collect::<Vec<_>>();
+        Self::from_points(transformed.iter())
+    }
+
+    /// Check whether a ray hits the box for some t between t_min and t_max
+    pub fn intersects(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+
+    /// The range of t between t_min and t_max for which the ray is inside of the box
+    pub fn clip(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let axes = [
+            (
+                ray.origin.x(),
+                ray.direction.x(),
+                self.min.x(),
+                self.max.x(),
+            ),
+            (
+                ray.origin.y(),
+                ray.direction.y(),
+                self.min.y(),
+                self.max.y(),
+            ),
+            (
+                ray.origin.z(),
+                ray.direction.z(),
+                self.min.z(),
+                self.max.z(),
+            ),
+        ];
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for &(origin, direction, min, max) in axes.iter() {
+            if direction == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+            } else {
+                let t0 = (min - origin) / direction;
+                let t1 = (max - origin) / direction;
+                let (t0, t1) = if t0 > t1 { (t1, t0) } else { (t0, t1) };
+                t_min = t_min.max(t0);
+                t_max = t_max.min(t1);
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+        Some((t_min, t_max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assert_approx_eq, primitives::vector::vector};
+    use std::f64::consts;
+
+    #[test]
+    fn from_points() {
+        let b = BoundingBox::from_points(
+            [point(-5., 2., 0.), point(7., 0., -3.), point(0., 3., 1.)].iter(),
+        );
+        assert_approx_eq!(b.min, &point(-5., 0., -3.));
+        assert_approx_eq!(b.max, &point(7., 3., 1.));
+    }
+
+    #[test]
+    fn merge() {
+        let b1 = BoundingBox::new(point(-5., -2., 0.), point(7., 4., 4.));
+        let b2 = BoundingBox::new(point(8., -7., -2.), point(14., 2., 8.));
+        let b = b1.merge(&b2);
+        assert_approx_eq!(b.min, &point(-5., -7., -2.));
+        assert_approx_eq!(b.max, &point(14., 4., 8.));
+    }
+
+    #[test]
+    fn contains() {
+        let b = BoundingBox::new(point(5., -2., 0.), point(11., 4., 7.));
+        assert!(b.contains_point(&point(5., -2., 0.)));
+        assert!(b.contains_point(&point(8., 1., 3.)));
+        assert!(!b.contains_point(&point(3., 0., 3.)));
+        assert!(!b.contains_point(&point(8., 1., 8.)));
+        assert!(b.contains_box(&BoundingBox::new(point(6., -1., 1.), point(10., 3., 6.))));
+        assert!(!b.contains_box(&BoundingBox::new(point(4., -3., -1.), point(10., 3., 6.))));
+    }
+
+    // expected values rounded to a few digits
+    #[allow(clippy::approx_constant)]
+    #[test]
+    fn transform() {
+        let b = BoundingBox::new(point(-1., -1., -1.), point(1., 1., 1.));
+        let t = Transformation::new_y_rotation(consts::FRAC_PI_4).rotated_x(consts::FRAC_PI_4);
+        let b2 = b.transform(&t);
+        assert_approx_eq!(b2.min, &point(-1.41421, -1.70710, -1.70710));
+        assert_approx_eq!(b2.max, &point(1.41421, 1.70710, 1.70710));
+        let infinite = BoundingBox::infinite().transform(&t);
+        assert!(!infinite.is_finite());
+    }
+
+    #[test]
+    fn intersects() {
+        let b = BoundingBox::new(point(5., -2., 0.), point(11., 4., 7.));
+        let examples = vec![
+            (point(15., 1., 2.), vector(-1., 0., 0.), true),
+            (point(-5., -1., 4.), vector(1., 0., 0.), true),
+            (point(7., 6., 5.), vector(0., -1., 0.), true),
+            (point(9., 0., 7.), vector(0., 0., -1.), true),
+            (point(8., 2., 12.), vector(0., 0., -1.), true),
+            (point(6., 0., 5.), vector(0., 0., 1.), true),
+            (point(9., -1., -8.), vector(2., 4., 6.), false),
+            (point(8., 3., -4.), vector(6., 2., 4.), false),
+            (point(9., -1., -2.), vector(4., 6., 2.), false),
+            (point(4., 0., 9.), vector(0., 0., -1.), false),
+            (point(8., 6., -1.), vector(0., -1., 0.), false),
+            (point(12., 5., 4.), vector(-1., 0., 0.), false),
+        ];
+        for (origin, direction, expected) in examples.into_iter() {
+            let r = Ray::new(origin, direction.unit());
+            assert_eq!(b.intersects(&r, -f64::INFINITY, f64::INFINITY), expected);
+        }
+        let r = Ray::new(point(15., 1., 2.), vector(-1., 0., 0.));
+        assert!(!b.intersects(&r, 0., 3.));
+    }
+}
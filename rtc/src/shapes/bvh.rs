@@ -0,0 +1,193 @@
+use std::{cmp::Ordering, sync::Arc};
+
+use super::{BoundingBox, Intersection, Shape};
+
+use crate::primitives::{ray::Ray, vector::Point};
+
+/// Shapes with at most this many primitives are never split
+const MAX_LEAF_SIZE: usize = 4;
+/// Relative cost of testing a node's bounding box compared to intersecting a primitive
+const TRAVERSAL_COST: f64 = 0.125;
+
+#[derive(Clone, Debug)]
+enum Node {
+    Leaf {
+        bounds: BoundingBox,
+        shapes: Vec<usize>,
+    },
+    Inner {
+        bounds: BoundingBox,
+        left: usize,
+        right: usize,
+    },
+}
+
+impl Node {
+    fn bounds(&self) -> &BoundingBox {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// Bounding volume hierarchy over a list of shapes using the surface area heuristic.
+/// The hierarchy stores indices into the list it was built from, so it has to be rebuilt
+/// whenever that list (or the transformations of its shapes) changes.
+#[derive(Clone, Debug, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Shapes without finite bounds (e.g. planes) are tested against every ray
+    unbounded: Vec<usize>,
+}
+
+impl Bvh {
+    pub fn new(shapes: &[Arc<Shape>]) -> Self {
+        let mut bvh = Bvh::default();
+        let mut bounded = vec![];
+        for (i, shape) in shapes.iter().enumerate() {
+            let bounds = shape.bounds();
+            if bounds.is_finite() {
+                bounded.push((i, bounds.centroid(), bounds));
+            } else if !bounds.is_empty() {
+                bvh.unbounded.push(i);
+            }
+        }
+        if !bounded.is_empty() {
+            bvh.build(bounded);
+        }
+        bvh
+    }
+
+    /// Number of nodes in the hierarchy
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Bounds of all shapes in the hierarchy
+    pub fn bounds(&self) -> BoundingBox {
+        if !self.unbounded.is_empty() {
+            BoundingBox::infinite()
+        } else {
+            self.nodes
+                .first()
+                .map(|n| n.bounds().clone())
+                .unwrap_or_else(BoundingBox::empty)
+        }
+    }
+
+    /// Recursively build the subtree for `items`, returns the index of its root node
+    fn build(&mut self, mut items: Vec<(usize, Point, BoundingBox)>) -> usize {
+        let bounds = items
+            .iter()
+            .fold(BoundingBox::empty(), |b, (_, _, item)| b.merge(item));
+        let index = self.nodes.len();
+        let leaf = |items: &[(usize, _, _)]| Node::Leaf {
+            bounds: bounds.clone(),
+            shapes: items.iter().map(|(i, _, _)| *i).collect(),
+        };
+        if items.len() <= MAX_LEAF_SIZE {
+            self.nodes.push(leaf(&items));
+            return index;
+        }
+
+        // split along the axis where the centroids are spread the most
+        let centroid_bounds = BoundingBox::from_points(items.iter().map(|(_, c, _)| c));
+        let extent = &centroid_bounds.max - &centroid_bounds.min;
+        let axis = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+            0
+        } else if extent.y() >= extent.z() {
+            1
+        } else {
+            2
+        };
+        items.sort_by(|(_, a, _), (_, b, _)| {
+            a[(axis, 0)]
+                .partial_cmp(&b[(axis, 0)])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        // evaluate the surface area heuristic for every possible split position
+        let n = items.len();
+        let mut right_areas = vec![0.; n];
+        let mut acc = BoundingBox::empty();
+        for i in (1..n).rev() {
+            acc = acc.merge(&items[i].2);
+            right_areas[i] = acc.surface_area();
+        }
+        let mut acc = BoundingBox::empty();
+        let mut best = (f64::INFINITY, n / 2);
+        for i in 1..n {
+            acc = acc.merge(&items[i - 1].2);
+            let cost = acc.surface_area() * i as f64 + right_areas[i] * (n - i) as f64;
+            if cost < best.0 {
+                best = (cost, i);
+            }
+        }
+        let area = bounds.surface_area();
+        let split_cost = TRAVERSAL_COST + best.0 / area;
+        if area > 0. && split_cost >= n as f64 {
+            self.nodes.push(leaf(&items));
+            return index;
+        }
+
+        // reserve the slot for this node, children are appended after it
+        self.nodes.push(Node::Leaf {
+            bounds: bounds.clone(),
+            shapes: vec![],
+        });
+        let right_items = items.split_off(best.1);
+        let left = self.build(items);
+        let right = self.build(right_items);
+        self.nodes[index] = Node::Inner {
+            bounds,
+            left,
+            right,
+        };
+        index
+    }
+
+    /// Find all intersections of the ray with the given shapes for which the ray enters the
+    /// bounding box of the shape somewhere between t_min and t_max. The intersections are unsorted
+    /// and may contain intersections outside of that interval.
+    pub fn intersect(
+        &self,
+        shapes: &[Arc<Shape>],
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Vec<Intersection> {
+        let mut xs = vec![];
+        let mut test = |i: usize| {
+            let shape = &shapes[i];
+            if let Some(is) = shape.intersect(ray) {
+                xs.extend(is);
+            }
+        };
+        for &i in self.unbounded.iter() {
+            test(i);
+        }
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0]
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !node.bounds().intersects(ray, t_min, t_max) {
+                continue;
+            }
+            match node {
+                Node::Leaf { shapes, .. } => shapes.iter().for_each(|&i| test(i)),
+                Node::Inner { left, right, .. } => {
+                    stack.push(*right);
+                    stack.push(*left);
+                }
+            }
+        }
+        xs
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use super::{
+    cylinder::{intersect_caps, truncate},
+    prelude::*,
+};
+
+use crate::{
+    primitives::{
+        approx_eq::{ApproxEq, EPSILON_F64},
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A double napped cone around the y axis with its tips at the origin. The radius at some
+/// height y is |y|. Like cylinders, cones may be cut off at `minimum` and `maximum` and capped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cone {
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Default for Cone {
+    /// An infinite cone
+    fn default() -> Self {
+        Cone {
+            minimum: -f64::INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Geometry for Cone {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let (o, d) = (&ray.origin, &ray.direction);
+        let a = d.x().powi(2) - d.y().powi(2) + d.z().powi(2);
+        let b = 2.0 * (o.x() * d.x() - o.y() * d.y() + o.z() * d.z());
+        let c = o.x().powi(2) - o.y().powi(2) + o.z().powi(2);
+        let ts = if a.approx_eq(0.0) {
+            if b.approx_eq(0.0) {
+                vec![]
+            } else {
+                // the ray is parallel to one of the halves and only hits the other one
+                vec![-c / (2.0 * b)]
+            }
+        } else {
+            let disc = b.powi(2) - 4.0 * a * c;
+            if disc < 0.0 {
+                vec![]
+            } else {
+                let t0 = (-b - disc.sqrt()) / (2.0 * a);
+                let t1 = (-b + disc.sqrt()) / (2.0 * a);
+                vec![t0, t1]
+            }
+        };
+        let mut ts = truncate(ray, ts, self.minimum, self.maximum);
+        if self.closed {
+            ts.extend(intersect_caps(ray, self.minimum, self.maximum, f64::abs));
+        }
+        if ts.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                ts.into_iter()
+                    .map(|t| Intersection::new(t, Arc::clone(shape)))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        let dist = point.x().powi(2) + point.z().powi(2);
+        Some(
+            if dist < self.maximum.powi(2) && point.y() >= self.maximum - EPSILON_F64 {
+                vector(0., 1., 0.)
+            } else if dist < self.minimum.powi(2) && point.y() <= self.minimum + EPSILON_F64 {
+                vector(0., -1., 0.)
+            } else {
+                let y = dist.sqrt();
+                let y = if point.y() > 0. { -y } else { y };
+                vector(point.x(), y, point.z())
+            },
+        )
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let r = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(point(-r, self.minimum, -r), point(r, self.maximum, r))
+    }
+}
+
+impl Shape {
+    pub fn new_cone(material: Material, transform: Transformation) -> Self {
+        Self::new_truncated_cone(-f64::INFINITY, f64::INFINITY, false, material, transform)
+    }
+
+    pub fn default_cone() -> Self {
+        Self::new_cone(Material::default(), Transformation::identity())
+    }
+
+    /// A cone extending from y = `minimum` to y = `maximum`, optionally with caps
+    pub fn new_truncated_cone(
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(
+            Cone {
+                minimum,
+                maximum,
+                closed,
+            },
+            material,
+            transform,
+        )
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{ray::Ray, vector::Transformation},
+    shading::Material,
+};
+
+/// The ways two shapes can be combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Everything that's in either shape
+    Union,
+    /// Everything that's in both shapes
+    Intersection,
+    /// Everything that's in the left but not in the right shape
+    Difference,
+}
+
+impl CsgOperation {
+    /// Decide whether an intersection is part of the surface of the combined shape.
+    /// `left_hit` is true if the left shape was hit, `in_left` and `in_right` tell
+    /// whether the intersection happened inside of the left/right shape.
+    pub fn intersection_allowed(self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            CsgOperation::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            CsgOperation::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// Constructive solid geometry: two shapes combined by some operation
+#[derive(Clone, Debug)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Arc<Shape>,
+    pub right: Arc<Shape>,
+}
+
+impl Csg {
+    /// Only keep the intersections that lie on the surface of the combined shape.
+    /// The intersections have to be sorted.
+    pub fn filter_intersections(&self, xs: Intersections) -> Vec<Intersection> {
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut result = vec![];
+        for i in xs.into_iter() {
+            let left_hit = self.left.includes(&i);
+            if self
+                .operation
+                .intersection_allowed(left_hit, in_left, in_right)
+            {
+                result.push(i);
+            }
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+        result
+    }
+}
+
+impl Geometry for Csg {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        self.world_intersect(shape, &ray.transform(&shape.world_transform()))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let left = self.left.parent_space_bounds();
+        match self.operation {
+            CsgOperation::Difference => left,
+            _ => left.merge(&self.right.parent_space_bounds()),
+        }
+    }
+
+    fn world_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        // Like for groups the transformation of the CSG shape is part of its children already
+        let xs = vec![&self.left, &self.right]
+            .into_iter()
+            .filter_map(|child| child.intersect(ray))
+            .flat_map(Intersections::into_iter)
+            .collect::<Vec<_>>();
+        let xs = self
+            .filter_intersections(Intersections::new(xs))
+            .into_iter()
+            .map(|mut i| {
+                i.medium = Some(Arc::clone(shape));
+                i
+            })
+            .collect::<Vec<_>>();
+        if xs.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(xs))
+        }
+    }
+
+    fn world_bounds(&self, _shape: &Shape) -> BoundingBox {
+        let left = self.left.bounds();
+        match self.operation {
+            CsgOperation::Difference => left,
+            _ => left.merge(&self.right.bounds()),
+        }
+    }
+
+    fn update_world_transform(&mut self, world_transform: &Transformation) {
+        Arc::make_mut(&mut self.left).set_parent_transform(world_transform.clone());
+        Arc::make_mut(&mut self.right).set_parent_transform(world_transform.clone());
+    }
+
+    fn includes(&self, hit: &Intersection) -> bool {
+        self.left.includes(hit) || self.right.includes(hit)
+    }
+}
+
+impl Shape {
+    pub fn new_csg(
+        operation: CsgOperation,
+        left: Shape,
+        right: Shape,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(
+            Csg {
+                operation,
+                left: Arc::new(left),
+                right: Arc::new(right),
+            },
+            Material::default(),
+            transform,
+        )
+    }
+}
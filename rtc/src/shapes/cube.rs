@@ -5,12 +5,14 @@ use super::prelude::*;
 use crate::{
     primitives::{
         ray::Ray,
-        vector::{vector, Point, Transformation, Vec3D},
+        vector::{point, vector, Point, Transformation, Vec3D},
     },
     shading::Material,
 };
 
-pub static CUBE: ShapeFuncs = (intersect, normal_at);
+/// An axis aligned cube spanning from -1 to 1 on every axis
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cube;
 
 fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
     let tmin_numerator = -1. - origin;
@@ -33,8 +35,8 @@ fn trimin(a: f64, b: f64, c: f64) -> f64 {
     a.min(b.min(c))
 }
 
-fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
-    base_shape_intersect(shape, ray, |shape, ray| {
+impl Geometry for Cube {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
         let (xtmin, xtmax) = check_axis(ray.origin.x(), ray.direction.x());
         let (ytmin, ytmax) = check_axis(ray.origin.y(), ray.direction.y());
         let (ztmin, ztmax) = check_axis(ray.origin.z(), ray.direction.z());
@@ -44,30 +46,32 @@ fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
             None
         } else {
             Some(Intersections::new(vec![
-                Intersection::new(tmin, Arc::clone(&shape)),
-                Intersection::new(tmax, Arc::clone(&shape)),
+                Intersection::new(tmin, Arc::clone(shape)),
+                Intersection::new(tmax, Arc::clone(shape)),
             ]))
         }
-    })
-}
+    }
 
-fn normal_at(shape: Arc<Shape>, point: &Point) -> Vec3D {
-    base_shape_normal(shape, point, |_, point| {
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
         let ax = point.x().abs();
         let ay = point.y().abs();
         let az = point.z().abs();
         let maxc = trimax(ax, ay, az);
-        match maxc {
+        Some(match maxc {
             x if x == ax => vector(point.x(), 0., 0.),
             x if x == ay => vector(0., point.y(), 0.),
             _ => vector(0., 0., point.z()),
-        }
-    })
+        })
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1., -1., -1.), point(1., 1., 1.))
+    }
 }
 
 impl Shape {
     pub fn new_cube(material: Material, transform: Transformation) -> Self {
-        Self::new(CUBE, material, transform)
+        Self::new(Cube, material, transform)
     }
 
     pub fn default_cube() -> Self {
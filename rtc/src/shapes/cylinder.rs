@@ -4,88 +4,149 @@ use super::prelude::*;
 
 use crate::{
     primitives::{
-        approx_eq::ApproxEq,
+        approx_eq::{ApproxEq, EPSILON_F64},
         ray::Ray,
-        vector::{vector, Point, Transformation, Vec3D},
+        vector::{point, vector, Point, Transformation, Vec3D},
     },
     shading::Material,
 };
 
-pub static CYLINDER: ShapeFuncs = (intersect, normal_at);
-pub static CYLINDER_TRUNC: ShapeFuncs = (intersect_trunc, normal_at);
+/// A cylinder of radius 1 around the y axis, cut off at `minimum` and `maximum` (both
+/// exclusive). If it's closed the ends are capped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cylinder {
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
 
-fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
-    base_shape_intersect(shape, ray, |shape, ray| {
-        let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
-        if a.approx_eq(0.0) {
-            None
-        } else {
-            let b = 2.0 * (ray.origin.x() * ray.direction.x() + ray.origin.z() * ray.direction.z());
-            let c = ray.origin.x().powi(2) + ray.origin.z().powi(2) - 1.0;
-            let disc = b.powi(2) - 4.0 * a * c;
-            if disc < 0.0 {
-                None
-            } else {
-                let t0 = (-b - disc.sqrt()) / (2.0 * a);
-                let t1 = (-b + disc.sqrt()) / (2.0 * a);
-                let xs = Intersections::new(vec![
-                    Intersection::new(t0, Arc::clone(&shape)),
-                    Intersection::new(t1, Arc::clone(&shape)),
-                ]);
-                Some(xs)
-            }
+impl Default for Cylinder {
+    /// An infinite cylinder
+    fn default() -> Self {
+        Cylinder {
+            minimum: -f64::INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
         }
-    })
+    }
+}
+
+/// Check whether the intersection at t is within the given radius of the y axis
+fn check_cap(ray: &Ray, t: f64, radius: f64) -> bool {
+    let x = ray.origin.x() + t * ray.direction.x();
+    let z = ray.origin.z() + t * ray.direction.z();
+    x.powi(2) + z.powi(2) <= radius.powi(2)
+}
+
+/// Intersections of an object space ray with the caps at y = `minimum` and y = `maximum`,
+/// `radius` gives the radius of the cap at some height.
+pub(super) fn intersect_caps(
+    ray: &Ray,
+    minimum: f64,
+    maximum: f64,
+    radius: impl Fn(f64) -> f64,
+) -> Vec<f64> {
+    if ray.direction.y().approx_eq(0.0) {
+        return vec![];
+    }
+    vec![minimum, maximum]
+        .into_iter()
+        .filter(|y| y.is_finite())
+        .map(|y| (y, (y - ray.origin.y()) / ray.direction.y()))
+        .filter(|&(y, t)| check_cap(ray, t, radius(y)))
+        .map(|(_, t)| t)
+        .collect()
 }
 
-fn normal_at(shape: Arc<Shape>, point: &Point) -> Vec3D {
-    base_shape_normal(shape, point, |_, point| vector(point.x(), 0., point.z()))
+/// Only keep those intersections with the side of the shape that lie strictly between
+/// `minimum` and `maximum`
+pub(super) fn truncate(ray: &Ray, ts: Vec<f64>, minimum: f64, maximum: f64) -> Vec<f64> {
+    ts.into_iter()
+        .filter(|t| {
+            let y = ray.origin.y() + t * ray.direction.y();
+            minimum < y && y < maximum
+        })
+        .collect()
 }
 
-fn intersect_trunc(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
-    base_shape_intersect(shape, ray, |shape, ray| {
+impl Geometry for Cylinder {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let mut ts = vec![];
         let a = ray.direction.x().powi(2) + ray.direction.z().powi(2);
-        if a.approx_eq(0.0) {
-            None
-        } else {
+        if !a.approx_eq(0.0) {
             let b = 2.0 * (ray.origin.x() * ray.direction.x() + ray.origin.z() * ray.direction.z());
             let c = ray.origin.x().powi(2) + ray.origin.z().powi(2) - 1.0;
             let disc = b.powi(2) - 4.0 * a * c;
-            if disc < 0.0 {
-                None
-            } else {
+            if disc >= 0.0 {
                 let t0 = (-b - disc.sqrt()) / (2.0 * a);
                 let t1 = (-b + disc.sqrt()) / (2.0 * a);
-
-                let mut xs = vec![];
-                let y0 = ray.origin.y() + t0 * ray.direction.y();
-                if -1.0 < y0 && y0 < 1.0 {
-                    xs.push(Intersection::new(t0, Arc::clone(&shape)));
-                }
-                let y1 = ray.origin.y() + t1 * ray.direction.y();
-                if -1.0 < y1 && y1 < 1.0 {
-                    xs.push(Intersection::new(t1, Arc::clone(&shape)));
-                }
-                Some(Intersections::new(xs))
+                ts = truncate(ray, vec![t0, t1], self.minimum, self.maximum);
             }
         }
-    })
+        if self.closed {
+            ts.extend(intersect_caps(ray, self.minimum, self.maximum, |_| 1.0));
+        }
+        if ts.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                ts.into_iter()
+                    .map(|t| Intersection::new(t, Arc::clone(shape)))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        let dist = point.x().powi(2) + point.z().powi(2);
+        Some(if dist < 1.0 && point.y() >= self.maximum - EPSILON_F64 {
+            vector(0., 1., 0.)
+        } else if dist < 1.0 && point.y() <= self.minimum + EPSILON_F64 {
+            vector(0., -1., 0.)
+        } else {
+            vector(point.x(), 0., point.z())
+        })
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1., self.minimum, -1.), point(1., self.maximum, 1.))
+    }
 }
 
 impl Shape {
     pub fn new_cylinder(material: Material, transform: Transformation) -> Self {
-        Self::new(CYLINDER, material, transform)
+        Self::new_truncated_cylinder(-f64::INFINITY, f64::INFINITY, false, material, transform)
     }
 
     pub fn default_cylinder() -> Self {
         Self::new_cylinder(Material::default(), Transformation::identity())
     }
 
+    /// A cylinder extending from y = `minimum` to y = `maximum`, optionally with caps
+    pub fn new_truncated_cylinder(
+        minimum: f64,
+        maximum: f64,
+        closed: bool,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(
+            Cylinder {
+                minimum,
+                maximum,
+                closed,
+            },
+            material,
+            transform,
+        )
+    }
+
+    /// An open cylinder extending from y = -1 to y = 1
     pub fn new_trunc_cylinder(material: Material, transform: Transformation) -> Self {
-        Self::new(CYLINDER, material, transform)
+        Self::new_truncated_cylinder(-1., 1., false, material, transform)
     }
 
     pub fn default_trunc_cylinder() -> Self {
-        Self::new_cylinder(Material::default(), Transformation::identity())
+        Self::new_trunc_cylinder(Material::default(), Transformation::identity())
     }
 }
@@ -0,0 +1,105 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        approx_eq::EPSILON_F64,
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A disk in the xz plane around the origin facing up. With an inner radius larger than zero it
+/// becomes an annulus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Disk {
+    pub radius: f64,
+    pub inner_radius: f64,
+}
+
+impl Default for Disk {
+    /// The unit disk
+    fn default() -> Self {
+        Disk {
+            radius: 1.,
+            inner_radius: 0.,
+        }
+    }
+}
+
+impl Disk {
+    /// Surface coordinates of a point on the disk. u is the angle around the y axis (starting at
+    /// the positive x axis, turning towards negative z) and v the distance from the inner to the
+    /// outer edge, both between 0 and 1.
+    pub fn uv_at(&self, point: &Point) -> (f64, f64) {
+        let angle = (-point.z()).atan2(point.x());
+        let u = angle.rem_euclid(2. * PI) / (2. * PI);
+        let r = (point.x().powi(2) + point.z().powi(2)).sqrt();
+        let v = (r - self.inner_radius) / (self.radius - self.inner_radius);
+        (u, v.clamp(0., 1.))
+    }
+}
+
+impl Geometry for Disk {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        if ray.direction.y().abs() < EPSILON_F64 {
+            return None;
+        }
+        let t = -ray.origin.y() / ray.direction.y();
+        let p = ray.position(t);
+        let dist = p.x().powi(2) + p.z().powi(2);
+        if dist > self.radius.powi(2) || dist < self.inner_radius.powi(2) {
+            return None;
+        }
+        let (u, v) = self.uv_at(&p);
+        Some(Intersections::new(vec![Intersection::new_with_uv(
+            t,
+            Arc::clone(shape),
+            u,
+            v,
+        )]))
+    }
+
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(vector(0., 1., 0.))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let r = self.radius;
+        BoundingBox::new(point(-r, 0., -r), point(r, 0., r))
+    }
+}
+
+impl Shape {
+    /// A disk with the given radius in the xz plane
+    pub fn new_disk(radius: f64, material: Material, transform: Transformation) -> Self {
+        Self::new_annulus(0., radius, material, transform)
+    }
+
+    pub fn default_disk() -> Self {
+        Self::new_disk(1., Material::default(), Transformation::identity())
+    }
+
+    /// A ring in the xz plane between `inner_radius` and `radius`
+    pub fn new_annulus(
+        inner_radius: f64,
+        radius: f64,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        assert!(
+            0. <= inner_radius && inner_radius < radius,
+            "The inner radius of an annulus must be between 0 and its radius."
+        );
+        Self::new(
+            Disk {
+                radius,
+                inner_radius,
+            },
+            material,
+            transform,
+        )
+    }
+}
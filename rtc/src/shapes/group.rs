@@ -0,0 +1,107 @@
+use std::sync::{Arc, OnceLock};
+
+use super::{prelude::*, Bvh};
+
+use crate::{
+    primitives::{ray::Ray, vector::Transformation},
+    shading::Material,
+};
+
+/// A collection of shapes that share a common transformation
+#[derive(Clone, Debug, Default)]
+pub struct Group {
+    children: Vec<Arc<Shape>>,
+    /// Built when the group is first intersected or bounded, after the transformations of the
+    /// children have been set
+    bvh: OnceLock<Bvh>,
+}
+
+impl Group {
+    pub fn new(children: Vec<Arc<Shape>>) -> Self {
+        Group {
+            children,
+            bvh: OnceLock::new(),
+        }
+    }
+
+    pub fn children(&self) -> &[Arc<Shape>] {
+        &self.children
+    }
+
+    /// The children for changing them, the bounding volume hierarchy is rebuilt afterwards
+    pub fn children_mut(&mut self) -> impl Iterator<Item = &mut Arc<Shape>> {
+        self.bvh.take();
+        self.children.iter_mut()
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| Bvh::new(&self.children))
+    }
+}
+
+impl Geometry for Group {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        self.world_intersect(shape, &ray.transform(&shape.world_transform()))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.children
+            .iter()
+            .fold(BoundingBox::empty(), |bounds, child| {
+                bounds.merge(&child.parent_space_bounds())
+            })
+    }
+
+    fn world_intersect(&self, _shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        // The transformation of the group is already part of the world transformation of each
+        // child, so the world space ray is passed on as is.
+        let xs = self
+            .bvh()
+            .intersect(&self.children, ray, -f64::INFINITY, f64::INFINITY);
+        if xs.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(xs))
+        }
+    }
+
+    fn world_bounds(&self, _shape: &Shape) -> BoundingBox {
+        self.bvh().bounds()
+    }
+
+    fn update_world_transform(&mut self, world_transform: &Transformation) {
+        for child in self.children_mut() {
+            Arc::make_mut(child).set_parent_transform(world_transform.clone());
+        }
+    }
+
+    fn includes(&self, hit: &Intersection) -> bool {
+        self.children.iter().any(|c| c.includes(hit))
+    }
+
+    fn children(&self) -> &[Arc<Shape>] {
+        &self.children
+    }
+}
+
+impl Shape {
+    pub fn new_group(children: Vec<Shape>, transform: Transformation) -> Self {
+        let children = children.into_iter().map(Arc::new).collect();
+        Self::new(Group::new(children), Material::default(), transform)
+    }
+
+    pub fn default_group() -> Self {
+        Self::new_group(vec![], Transformation::identity())
+    }
+
+    /// Add a shape to a group, the shape inherits the transformations of the group
+    pub fn add_child(&mut self, mut child: Shape) {
+        let world_transform = self.parent_transform() * self.transform();
+        child.set_parent_transform(world_transform);
+        let group = self
+            .geometry_as_mut::<Group>()
+            .expect("Tried adding a child to a shape that isn't a group.");
+        group.children.push(Arc::new(child));
+        group.bvh.take();
+    }
+}
@@ -0,0 +1,251 @@
+use std::{path::Path, sync::Arc};
+
+use super::{prelude::*, triangle::intersect_triangle};
+
+use crate::{
+    parsers::{read_netpbm, NetpbmError},
+    primitives::{
+        canvas::Canvas,
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A terrain given by a regular grid of heights. In object space the grid spans from -1 to 1 along
+/// x and z, with heights between 0 and 1. Each grid cell is made up of two triangles.
+#[derive(Clone, Debug)]
+pub struct Heightfield {
+    /// Row major heights, rows run along z and columns along x
+    heights: Vec<f64>,
+    columns: usize,
+    rows: usize,
+    /// Normals at each grid point, interpolated across the cells
+    normals: Vec<Vec3D>,
+    /// Lowest and highest point of each cell, used to skip cells the ray passes above or below
+    cell_ranges: Vec<(f64, f64)>,
+    bounds: BoundingBox,
+}
+
+impl Heightfield {
+    /// A heightfield with `columns` samples along x and `rows` samples along z. There have to be
+    /// at least two of each.
+    pub fn new(heights: Vec<f64>, columns: usize, rows: usize) -> Self {
+        assert!(
+            columns >= 2 && rows >= 2,
+            "Heightfields need at least 2x2 samples."
+        );
+        assert_eq!(heights.len(), columns * rows);
+        let mut field = Heightfield {
+            heights,
+            columns,
+            rows,
+            normals: vec![],
+            cell_ranges: vec![],
+            bounds: BoundingBox::empty(),
+        };
+        field.normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| field.grid_normal(i, j))
+            .collect();
+        field.cell_ranges = (0..rows - 1)
+            .flat_map(|j| (0..columns - 1).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let hs = [
+                    field.height(i, j),
+                    field.height(i + 1, j),
+                    field.height(i, j + 1),
+                    field.height(i + 1, j + 1),
+                ];
+                let min = hs.iter().cloned().fold(f64::INFINITY, f64::min);
+                let max = hs.iter().cloned().fold(-f64::INFINITY, f64::max);
+                (min, max)
+            })
+            .collect();
+        let (min, max) = field
+            .cell_ranges
+            .iter()
+            .fold((f64::INFINITY, -f64::INFINITY), |(lo, hi), &(min, max)| {
+                (lo.min(min), hi.max(max))
+            });
+        field.bounds = BoundingBox::new(point(-1., min, -1.), point(1., max, 1.));
+        field
+    }
+
+    /// Use the brightness of each pixel as height, the first row of the image lies at z = -1
+    pub fn from_canvas(canvas: &Canvas) -> Self {
+        let heights = canvas
+            .iter()
+            .map(|p| f64::from(p.r + p.g + p.b) / 3.)
+            .collect();
+        Self::new(heights, canvas.width(), canvas.height())
+    }
+
+    /// Load the heights from a PGM or PPM image
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, NetpbmError> {
+        Ok(Self::from_canvas(&read_netpbm(path)?))
+    }
+
+    pub fn height(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.columns + i]
+    }
+
+    fn cells_x(&self) -> usize {
+        self.columns - 1
+    }
+
+    fn cells_z(&self) -> usize {
+        self.rows - 1
+    }
+
+    /// Object space position of a grid point
+    fn vertex(&self, i: usize, j: usize) -> Point {
+        point(
+            -1. + 2. * i as f64 / self.cells_x() as f64,
+            self.height(i, j),
+            -1. + 2. * j as f64 / self.cells_z() as f64,
+        )
+    }
+
+    /// Normal at a grid point from the central differences of the neighbouring heights
+    fn grid_normal(&self, i: usize, j: usize) -> Vec3D {
+        let (i0, i1) = (i.saturating_sub(1), (i + 1).min(self.columns - 1));
+        let (j0, j1) = (j.saturating_sub(1), (j + 1).min(self.rows - 1));
+        let spacing_x = 2. / self.cells_x() as f64;
+        let spacing_z = 2. / self.cells_z() as f64;
+        let dx = (self.height(i1, j) - self.height(i0, j)) / ((i1 - i0) as f64 * spacing_x);
+        let dz = (self.height(i, j1) - self.height(i, j0)) / ((j1 - j0) as f64 * spacing_z);
+        vector(-dx, 1., -dz).unit()
+    }
+
+    /// Position of an object space coordinate in grid units
+    fn to_grid(&self, x: f64, z: f64) -> (f64, f64) {
+        (
+            (x + 1.) / 2. * self.cells_x() as f64,
+            (z + 1.) / 2. * self.cells_z() as f64,
+        )
+    }
+
+    fn cell_of(&self, gx: f64, gz: f64) -> (usize, usize) {
+        let clamp = |g: f64, cells: usize| (g.floor().max(0.) as usize).min(cells - 1);
+        (clamp(gx, self.cells_x()), clamp(gz, self.cells_z()))
+    }
+
+    /// Intersections of an object space ray with the triangles of the cell at (i, j)
+    fn intersect_cell(&self, ray: &Ray, i: usize, j: usize) -> Vec<f64> {
+        let p00 = self.vertex(i, j);
+        let p10 = self.vertex(i + 1, j);
+        let p01 = self.vertex(i, j + 1);
+        let p11 = self.vertex(i + 1, j + 1);
+        vec![
+            intersect_triangle(ray, &p00, &p10, &p11),
+            intersect_triangle(ray, &p00, &p11, &p01),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|(t, _, _)| t)
+        .collect()
+    }
+
+    /// Walk through the cells of the grid pierced by the ray (using a 2D DDA) and intersect the
+    /// ray with each of them
+    fn traverse(&self, ray: &Ray) -> Vec<f64> {
+        let (t_enter, t_exit) = match self.bounds.clip(ray, -f64::INFINITY, f64::INFINITY) {
+            Some(range) => range,
+            None => return vec![],
+        };
+        let start = ray.position(t_enter);
+        let (gx, gz) = self.to_grid(start.x(), start.z());
+        let (mut i, mut j) = self.cell_of(gx, gz);
+
+        // change of the grid coordinates per unit of t
+        let dgx = ray.direction.x() / 2. * self.cells_x() as f64;
+        let dgz = ray.direction.z() / 2. * self.cells_z() as f64;
+        let axis = |g: f64, cell: usize, dg: f64| -> (i64, f64, f64) {
+            if dg > 0. {
+                (1, t_enter + ((cell + 1) as f64 - g) / dg, 1. / dg)
+            } else if dg < 0. {
+                (-1, t_enter + (cell as f64 - g) / dg, -1. / dg)
+            } else {
+                (0, f64::INFINITY, f64::INFINITY)
+            }
+        };
+        let (step_x, mut t_next_x, t_delta_x) = axis(gx, i, dgx);
+        let (step_z, mut t_next_z, t_delta_z) = axis(gz, j, dgz);
+
+        let mut ts = vec![];
+        let mut t_cell = t_enter;
+        loop {
+            let t_cell_exit = t_next_x.min(t_next_z).min(t_exit);
+            let (min, max) = self.cell_ranges[j * self.cells_x() + i];
+            let y0 = ray.position(t_cell).y();
+            let y1 = ray.position(t_cell_exit).y();
+            if !(y0.max(y1) < min || y0.min(y1) > max) {
+                ts.extend(self.intersect_cell(ray, i, j));
+            }
+            if t_cell_exit >= t_exit {
+                break;
+            }
+            t_cell = t_cell_exit;
+            let (cell, step, cells) = if t_next_x < t_next_z {
+                t_next_x += t_delta_x;
+                (&mut i, step_x, self.cells_x())
+            } else {
+                t_next_z += t_delta_z;
+                (&mut j, step_z, self.cells_z())
+            };
+            let next = *cell as i64 + step;
+            if next < 0 || next >= cells as i64 {
+                break;
+            }
+            *cell = next as usize;
+        }
+        // hits on edges shared by two triangles are found twice
+        ts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        ts.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+        ts
+    }
+}
+
+impl Geometry for Heightfield {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let ts = self.traverse(ray);
+        if ts.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                ts.into_iter()
+                    .map(|t| Intersection::new(t, Arc::clone(shape)))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        let (gx, gz) = self.to_grid(point.x(), point.z());
+        let (i, j) = self.cell_of(gx, gz);
+        let fx = (gx - i as f64).clamp(0., 1.);
+        let fz = (gz - j as f64).clamp(0., 1.);
+        let normal = |i: usize, j: usize| &self.normals[j * self.columns + i];
+        Some(
+            normal(i, j) * ((1. - fx) * (1. - fz))
+                + normal(i + 1, j) * (fx * (1. - fz))
+                + normal(i, j + 1) * ((1. - fx) * fz)
+                + normal(i + 1, j + 1) * (fx * fz),
+        )
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
+
+impl Shape {
+    pub fn new_heightfield(
+        heightfield: Heightfield,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(heightfield, material, transform)
+    }
+}
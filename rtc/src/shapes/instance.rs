@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        ray::Ray,
+        vector::Transformation,
+    },
+    shading::Material,
+};
+
+/// Another placement of a shared shape. The prototype is referenced rather than copied, so a mesh
+/// can be placed many times at the cost of a single shape each.
+///
+/// The world transformation of the prototype is part of the instance's object space: the
+/// instance's transformation is applied on top of it. Hits record the instances they went
+/// through and belong to the instance rather than to the shapes of the prototype.
+#[derive(Clone, Debug)]
+pub struct Instance {
+    prototype: Arc<Shape>,
+    /// Replaces the materials of all shapes in the prototype if set
+    pub material: Option<Material>,
+}
+
+impl Instance {
+    pub fn new(prototype: Arc<Shape>, material: Option<Material>) -> Self {
+        Instance {
+            prototype,
+            material,
+        }
+    }
+
+    pub fn prototype(&self) -> &Arc<Shape> {
+        &self.prototype
+    }
+}
+
+impl Geometry for Instance {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let xs = self.prototype.intersect(ray)?;
+        Some(Intersections::new(
+            xs.into_iter()
+                .map(|mut i| {
+                    i.instances.push(Arc::clone(shape));
+                    i
+                })
+                .collect(),
+        ))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.prototype.bounds()
+    }
+}
+
+impl Shape {
+    /// Place `prototype` once more with an additional transformation. If `material` is given it
+    /// is used for all shapes of the prototype.
+    pub fn new_instance(
+        prototype: Arc<Shape>,
+        material: Option<Material>,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(
+            Instance::new(prototype, material),
+            Material::default(),
+            transform,
+        )
+    }
+}
@@ -6,74 +6,135 @@ use crate::primitives::{
     vector::{Point, ScalarProd, Vec3D},
 };
 
-use super::prelude::*;
+use crate::shading::{Color, Material};
+
+use super::{prelude::*, Instance};
 
 #[derive(Clone, Debug)]
 pub struct Intersection {
     pub t: f64,
     pub object: Arc<Shape>,
+    /// Surface coordinates of the intersection (barycentric for triangles)
+    pub u: f64,
+    pub v: f64,
+    /// The outermost CSG shape the object is part of. A ray crossing the surface of the object
+    /// enters or leaves this shape rather than the object itself.
+    pub medium: Option<Arc<Shape>>,
+    /// The instances through which the object was hit, innermost first. Their transformations
+    /// have to be applied on top of the world transformation of the object.
+    pub instances: Vec<Arc<Shape>>,
+    /// Time of the ray that produced the intersection
+    pub time: f64,
 }
 
 impl Intersection {
     pub fn new(t: f64, object: Arc<Shape>) -> Self {
-        Intersection { t, object }
+        Self::new_with_uv(t, object, 0., 0.)
     }
 
-    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections) -> PreComp {
-        let point = ray.position(self.t);
-        let eye = -ray.direction.clone();
-        let mut normal = (self.object.normal_at)(self.object.clone(), &point);
-        let inside = (&normal).scalar_prod(&eye) < 0.;
-        if inside {
-            normal = -normal;
+    pub fn new_with_uv(t: f64, object: Arc<Shape>, u: f64, v: f64) -> Self {
+        Intersection {
+            t,
+            object,
+            u,
+            v,
+            medium: None,
+            instances: vec![],
+            time: 0.,
         }
-        // 10.0 is a factor that may be tweaked depending on visual artifacts
-        let over_point = &point + &normal * EPSILON_F64;
-        let under_point = &point - &normal * EPSILON_F64;
-        let reflection = ray.direction.reflect(&normal);
+    }
+
+    /// The shape whose inside the ray enters or leaves at this intersection
+    pub fn medium(&self) -> &Arc<Shape> {
+        self.medium.as_ref().unwrap_or(&self.object)
+    }
+
+    /// Check whether both intersections are with the same medium seen through the same instances
+    pub(crate) fn same_medium(&self, other: &Intersection) -> bool {
+        Arc::ptr_eq(self.medium(), other.medium())
+            && self.instances.len() == other.instances.len()
+            && self
+                .instances
+                .iter()
+                .zip(other.instances.iter())
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+    }
 
-        let mut containers: Vec<&Arc<Shape>> = Vec::new();
-        let mut n1 = None;
-        let mut n2 = None;
+    /// The material of the object, unless an instance overrides it
+    pub fn material(&self) -> &Material {
+        instance_material(&self.instances).unwrap_or(&self.object.material)
+    }
+
+    /// Convert a point from world space to the object space of the object
+    pub fn world_to_object(&self, point: &Point) -> Point {
+        let point = instances_to_object(&self.instances, point, self.time);
+        self.object.world_to_object_at(&point, self.time)
+    }
+
+    /// Calculates the world space normal at some world space point on the object
+    pub fn normal_at(&self, point: &Point) -> Vec3D {
+        let point = instances_to_object(&self.instances, point, self.time);
+        let normal = self
+            .object
+            .normal_at(&point, self)
+            .expect("Intersections refer to shapes with a surface.");
+        self.instances.iter().fold(normal, |normal, instance| {
+            instance.normal_to_world_at(&normal, self.time)
+        })
+    }
+
+    pub fn prepare_computations(&self, ray: &Ray, xs: &Intersections) -> PreComp {
+        // The media the ray is currently inside of, together with their materials. For CSG
+        // shapes the material of the surface the ray entered through is used.
+        let mut containers: Vec<(&Intersection, &Material)> = Vec::new();
+        let mut medium1 = None;
+        let mut medium2 = None;
         for intersection in xs.iter() {
             // equivalent to self as *const _ == intersection as *const _
             let i_eq_hit = std::ptr::eq(self, intersection);
             if i_eq_hit {
-                // dbg!(self, intersection);
-                n1 = containers.last().map(|o| o.material.refractive_index);
+                medium1 = containers.last().map(|(_, m)| *m);
             }
 
-            // Find the position of the current object in containers
+            // Find the position of the current medium in containers
             if let Some(position) = containers
                 .iter()
-                .position(|&x| Arc::ptr_eq(x, &intersection.object))
+                .position(|(x, _)| x.same_medium(intersection))
             {
                 // remove it if it's in there
                 containers.remove(position);
             } else {
                 // add it if it isn't
-                containers.push(&intersection.object);
+                containers.push((intersection, intersection.material()));
             }
             if i_eq_hit {
-                n2 = containers.last().map(|o| o.material.refractive_index);
+                medium2 = containers.last().map(|(_, m)| *m);
                 break;
             }
         }
-        PreComp::new(
-            point,
-            eye,
-            normal,
-            reflection,
-            self.clone(),
-            inside,
-            over_point,
-            under_point,
-            n1.unwrap_or(1.0),
-            n2.unwrap_or(1.0),
-        )
+        PreComp::new(ray, self.clone(), medium1, medium2)
     }
 }
 
+/// The material override of the outermost instance that has one
+fn instance_material(instances: &[Arc<Shape>]) -> Option<&Material> {
+    instances.iter().rev().find_map(|instance| {
+        instance
+            .geometry_as::<Instance>()
+            .and_then(|i| i.material.as_ref())
+    })
+}
+
+/// Take a world space point into the space the innermost instance places its prototype in
+fn instances_to_object(instances: &[Arc<Shape>], point: &Point, time: f64) -> Point {
+    instances
+        .iter()
+        .rev()
+        .fold(point.clone(), |point, instance| {
+            instance.world_to_object_at(&point, time)
+        })
+}
+
 impl ApproxEq for Intersection {
     fn approx_eq(self, other: Self) -> bool {
         self.t.approx_eq(other.t) && self.object.approx_eq(&other.object)
@@ -99,10 +160,21 @@ impl Intersections {
         Intersections { is }
     }
 
+    /// Set the time of all intersections to the time of the ray that produced them
+    pub(crate) fn set_time(&mut self, time: f64) {
+        for i in self.is.iter_mut() {
+            i.time = time;
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.is.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.is.is_empty()
+    }
+
     pub fn hit(&self) -> Option<&Intersection> {
         self.is.iter().fold(None, |old, new| {
             if let Some(o) = old {
@@ -122,8 +194,13 @@ impl Intersections {
     pub fn iter(&self) -> impl Iterator<Item = &Intersection> {
         self.is.iter()
     }
+}
+
+impl IntoIterator for Intersections {
+    type Item = Intersection;
+    type IntoIter = std::vec::IntoIter<Intersection>;
 
-    pub fn into_iter(self) -> impl Iterator<Item = Intersection> {
+    fn into_iter(self) -> Self::IntoIter {
         self.is.into_iter()
     }
 }
@@ -154,39 +231,97 @@ pub struct PreComp {
     pub reflection: Vec3D,
     pub t: f64,
     pub object: Arc<Shape>,
+    /// The instances through which the object was hit, innermost first
+    pub instances: Vec<Arc<Shape>>,
+    /// Time of the ray that hit the object
+    pub time: f64,
     pub inside: bool,
     pub over_point: Point,
     pub under_point: Point,
     pub n1: f32,
     pub n2: f32,
+    /// Absorption of the medium on the near side of the surface, which reflected rays travel
+    /// through
+    pub absorption1: Color,
+    /// Absorption of the medium on the far side of the surface, which refracted rays travel
+    /// through
+    pub absorption2: Color,
+    /// Surface coordinates of the hit used by textures
+    pub uv: (f64, f64),
 }
 
 impl PreComp {
+    /// The values of an intersection of some ray. `medium1` is the material of the medium the ray
+    /// travels through and `medium2` that of the medium on the other side of the surface, `None`
+    /// stands for vacuum.
     pub fn new(
-        point: Point,
-        eye: Vec3D,
-        normal: Vec3D,
-        reflection: Vec3D,
+        ray: &Ray,
         intersection: Intersection,
-        inside: bool,
-        over_point: Point,
-        under_point: Point,
-        n1: f32,
-        n2: f32,
+        medium1: Option<&Material>,
+        medium2: Option<&Material>,
     ) -> Self {
+        let point = ray.position(intersection.t);
+        let eye = -ray.direction.clone();
+        let mut normal = intersection.normal_at(&point);
+        let inside = (&normal).scalar_prod(&eye) < 0.;
+        if inside {
+            normal = -normal;
+        }
+        // 10.0 is a factor that may be tweaked depending on visual artifacts
+        let over_point = &point + &normal * EPSILON_F64;
+        let under_point = &point - &normal * EPSILON_F64;
+        let reflection = ray.direction.reflect(&normal);
+        let uv = intersection.object.texture_uv(&intersection);
         PreComp {
             point,
             eye,
             normal,
             t: intersection.t,
             object: intersection.object,
+            instances: intersection.instances,
+            time: intersection.time,
             inside,
             over_point,
             reflection,
-            n1,
-            n2,
+            n1: medium1.map_or(1.0, |m| m.refractive_index),
+            n2: medium2.map_or(1.0, |m| m.refractive_index),
             under_point,
+            absorption1: medium1.map_or(Color::black(), |m| m.absorption),
+            absorption2: medium2.map_or(Color::black(), |m| m.absorption),
+            uv,
+        }
+    }
+
+    /// The material of the object, unless an instance overrides it
+    pub fn material(&self) -> &Material {
+        instance_material(&self.instances).unwrap_or(&self.object.material)
+    }
+
+    /// The color of the surface at the over point, taking patterns and textures into account
+    pub fn surface_color(&self) -> Color {
+        let material = self.material();
+        match &material.pattern {
+            Some(pattern) => {
+                let point = instances_to_object(&self.instances, &self.over_point, self.time);
+                let object_point = self.object.world_to_object_at(&point, self.time);
+                pattern.at_surface_point(&object_point, self.uv)
+            }
+            None => material.color,
+        }
+    }
+
+    /// Direction of the refracted ray according to Snell's law, `None` in case of total internal
+    /// reflection
+    pub fn refraction(&self) -> Option<Vec3D> {
+        let n_ratio = self.n1 / self.n2;
+        let cos_i = (&self.eye).scalar_prod(&self.normal);
+        let sin2_t = n_ratio.powi(2) as f64 * (1. - cos_i.powi(2));
+        if sin2_t > 1.0 {
+            return None;
         }
+        let n_ratio = n_ratio as f64;
+        let cos_t = (1.0 - sin2_t).sqrt();
+        Some(self.normal.clone() * (n_ratio * cos_i - cos_t) - self.eye.clone() * n_ratio)
     }
 
     pub fn schlick(&self) -> f64 {
@@ -1,15 +1,43 @@
+pub use bezier::*;
+pub use bounds::*;
+pub use bvh::*;
+pub use cone::*;
+pub use csg::*;
 pub use cube::*;
 pub use cylinder::*;
+pub use disk::*;
+pub use group::*;
+pub use heightfield::*;
+pub use instance::*;
 pub use intersection::*;
+pub use motion::*;
 pub use plane::*;
 pub use prelude::*;
+pub use rectangle::*;
+pub use sdf::*;
 pub use sphere::*;
+pub use torus::*;
+pub use triangle::*;
 
+mod bezier;
+mod bounds;
+mod bvh;
+mod cone;
+mod csg;
 mod cube;
 mod cylinder;
+mod disk;
+mod group;
+mod heightfield;
+mod instance;
 mod intersection;
+mod motion;
 mod plane;
 mod prelude;
+mod rectangle;
+mod sdf;
 mod sphere;
+mod torus;
+mod triangle;
 #[cfg(test)]
 mod tests;
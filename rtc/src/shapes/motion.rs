@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::primitives::{
+    approx_eq::EPSILON_F64, polynomial::solve_cubic, tmatrix::Determinant, vector::Transformation,
+};
+
+/// Number of bisection steps used to locate a singular transformation
+const BISECTIONS: usize = 50;
+
+/// Why a `Motion` can't be created or applied to a shape
+#[derive(Debug, Clone, PartialEq)]
+pub enum MotionError {
+    /// A motion without any keyframe
+    NoKeyframes,
+    /// The interpolated transformation can't be inverted at this point in time, e.g. when a
+    /// rotation by 180 degrees is given by just two keyframes
+    NotInvertible(f64),
+    /// Groups and CSG shapes can't move themselves, an instance of them can
+    CantMove,
+}
+
+impl fmt::Display for MotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MotionError::NoKeyframes => write!(f, "motions need at least one keyframe"),
+            MotionError::NotInvertible(time) => {
+                write!(f, "the transformation at time {} can't be inverted", time)
+            }
+            MotionError::CantMove => write!(
+                f,
+                "groups and CSG shapes can't move, use an instance of them instead"
+            ),
+        }
+    }
+}
+
+/// The transformation of a moving shape over time, given by keyframes. Between two keyframes the
+/// matrices are interpolated linearly, so every point of the shape travels along a straight line.
+/// Rotations thus need a few keyframes, otherwise the shape visibly shrinks halfway through.
+#[derive(Clone, Debug)]
+pub struct Motion {
+    keyframes: Vec<(f64, Transformation)>,
+}
+
+impl Motion {
+    /// Keyframes are given as pairs of time and transformation, in any order. Fails if there are
+    /// no keyframes or if the transformation can't be inverted at some point in time.
+    pub fn new(mut keyframes: Vec<(f64, Transformation)>) -> Result<Self, MotionError> {
+        if keyframes.is_empty() {
+            return Err(MotionError::NoKeyframes);
+        }
+        keyframes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        if let Some(time) = keyframes
+            .iter()
+            .find_map(|(t, m)| (m.det() == 0.).then_some(*t))
+        {
+            return Err(MotionError::NotInvertible(time));
+        }
+        for pair in keyframes.windows(2) {
+            let ((t0, m0), (t1, m1)) = (&pair[0], &pair[1]);
+            if let Some(s) = singular_between(m0, m1) {
+                return Err(MotionError::NotInvertible(t0 + s * (t1 - t0)));
+            }
+        }
+        Ok(Motion { keyframes })
+    }
+
+    /// Move from `start` at time 0 to `end` at time 1
+    pub fn linear(start: Transformation, end: Transformation) -> Result<Self, MotionError> {
+        Self::new(vec![(0., start), (1., end)])
+    }
+
+    pub fn keyframes(&self) -> &[(f64, Transformation)] {
+        &self.keyframes
+    }
+
+    /// The transformation at some point in time. Before the first and after the last keyframe
+    /// the shape rests.
+    pub fn at(&self, time: f64) -> Transformation {
+        let next = self.keyframes.iter().position(|(t, _)| *t > time);
+        match next {
+            Some(0) => self.keyframes[0].1.clone(),
+            None => self.keyframes.last().unwrap().1.clone(),
+            Some(i) => {
+                let (t0, m0) = &self.keyframes[i - 1];
+                let (t1, m1) = &self.keyframes[i];
+                let s = (time - t0) / (t1 - t0);
+                m0 * (1. - s) + m1 * s
+            }
+        }
+    }
+}
+
+/// Find a point `s` in (0, 1] where `m0 * (1 - s) + m1 * s` is singular, if there is one. The
+/// determinant is a polynomial of degree four in `s`. It has no root as long as it has the same
+/// sign at both ends and at its extrema in between. Extrema that only touch zero are rarely hit
+/// exactly by rounded matrices, so determinants far smaller than those of the keyframes count as
+/// singular as well.
+fn singular_between(m0: &Transformation, m1: &Transformation) -> Option<f64> {
+    let det = |s: f64| (m0 * (1. - s) + m1 * s).det();
+    // newton form of the polynomial through five samples, expanded to a*s^4 + ... + e
+    let nodes = [0., 0.25, 0.5, 0.75, 1.];
+    let mut diffs = nodes.map(det);
+    for k in 1..nodes.len() {
+        for i in (k..nodes.len()).rev() {
+            diffs[i] = (diffs[i] - diffs[i - 1]) / (nodes[i] - nodes[i - k]);
+        }
+    }
+    let mut coeffs = [0.; 5];
+    for i in (0..nodes.len()).rev() {
+        // coeffs = coeffs * (s - nodes[i]) + diffs[i], highest power first
+        for j in 0..4 {
+            coeffs[j] = coeffs[j + 1] - nodes[i] * coeffs[j];
+        }
+        coeffs[4] = diffs[i] - nodes[i] * coeffs[4];
+    }
+    // the keyframes are invertible, so the polynomial doesn't vanish
+    let largest = coeffs.iter().fold(0., |m: f64, c| m.max(c.abs()));
+    let [a, b, c, d, _] = coeffs.map(|c| c / largest);
+    let mut points = vec![0.];
+    points.extend(
+        solve_cubic(4. * a, 3. * b, 2. * c, d)
+            .into_iter()
+            .filter(|s| 0. < *s && *s < 1.),
+    );
+    points.push(1.);
+    let sign = det(0.).signum();
+    let tolerance = EPSILON_F64 * det(0.).abs().min(det(1.).abs());
+    let end = points.iter().position(|s| det(*s) * sign <= tolerance)?;
+    if det(points[end]) * sign > 0. {
+        return Some(points[end]);
+    }
+    // the determinant changes its sign, look for the zero crossing
+    let (mut lo, mut hi) = (points[end - 1], points[end]);
+    for _ in 0..BISECTIONS {
+        let mid = (lo + hi) / 2.;
+        if det(mid) * sign > 0. {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(hi)
+}
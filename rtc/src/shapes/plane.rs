@@ -6,30 +6,42 @@ use crate::{
     primitives::{
         approx_eq::EPSILON_F64,
         ray::Ray,
-        vector::{vector, Point, Transformation, Vec3D},
+        vector::{point, vector, Point, Transformation, Vec3D},
     },
     shading::Material,
 };
 
-pub static PLANE: ShapeFuncs = (intersect, normal_at);
+/// The infinite xz plane
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Plane;
 
-fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
-    base_shape_intersect(shape, ray, |shape, ray2| {
-        if ray2.direction.y().abs() < EPSILON_F64 {
+impl Geometry for Plane {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        if ray.direction.y().abs() < EPSILON_F64 {
             None
         } else {
-            let t = -ray2.origin.y() / ray2.direction.y();
-            Some(Intersections::new(vec![Intersection::new(t, shape)]))
+            let t = -ray.origin.y() / ray.direction.y();
+            Some(Intersections::new(vec![Intersection::new(
+                t,
+                Arc::clone(shape),
+            )]))
         }
-    })
-}
+    }
 
-fn normal_at(shape: Arc<Shape>, point: &Point) -> Vec3D {
-    base_shape_normal(shape, point, |_, _| vector(0., 1., 0.))
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(vector(0., 1., 0.))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            point(-f64::INFINITY, 0., -f64::INFINITY),
+            point(f64::INFINITY, 0., f64::INFINITY),
+        )
+    }
 }
 
 impl Shape {
     pub fn new_plane(material: Material, transform: Transformation) -> Self {
-        Self::new(PLANE, material, transform)
+        Self::new(Plane, material, transform)
     }
 }
@@ -1,4 +1,4 @@
-use std::{fmt, sync::Arc};
+use std::{any::Any, borrow::Cow, fmt, sync::Arc};
 
 use crate::{
     primitives::{
@@ -9,44 +9,195 @@ use crate::{
     shading::Material,
 };
 
-pub use super::{Intersection, Intersections};
+pub use super::{BoundingBox, Intersection, Intersections, Motion, MotionError};
 
-pub type ShapeFuncs = (IntersectFunc, NormalAtFunc);
-pub type IntersectFunc = fn(Arc<Shape>, &Ray) -> Option<Intersections>;
-pub type NormalAtFunc = fn(Arc<Shape>, &Point) -> Vec3D;
+use super::{Csg, Group};
+
+/// The geometry of a shape together with all parameters that can't be described by a
+/// transformation alone. Transformations and materials are handled by `Shape`, so geometries
+/// only have to work in object space.
+pub trait Geometry: GeometryBase + fmt::Debug + Send + Sync {
+    /// Intersect a ray given in object space with the geometry. `shape` is the shape owning the
+    /// geometry and is referenced by the returned intersections.
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections>;
+
+    /// Calculates the object space normal at some object space point on the geometry. The
+    /// intersection that produced the point is passed along for geometries that need additional
+    /// information like barycentric coordinates. Geometries without a surface of their own, like
+    /// groups whose intersections all refer to their children, keep the default of `None`. Every
+    /// geometry creating intersections that refer to its own shape has to return a normal.
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        None
+    }
+
+    /// The surface coordinates of a hit used by textures with mesh mapping. By default these are
+    /// the coordinates stored in the intersection.
+    fn texture_uv(&self, hit: &Intersection) -> (f64, f64) {
+        (hit.u, hit.v)
+    }
+
+    /// The object space axis aligned bounding box of the geometry
+    fn local_bounds(&self) -> BoundingBox;
+
+    /// Intersect a ray given in world space. Geometries containing other shapes override this
+    /// since the world transformations of their children already include their own.
+    fn world_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        self.local_intersect(
+            shape,
+            &ray.transform(&shape.world_inverse_transform_at(ray.time)),
+        )
+    }
+
+    /// The world space axis aligned bounding box of the geometry
+    fn world_bounds(&self, shape: &Shape) -> BoundingBox {
+        self.local_bounds().transform(&shape.world_transform())
+    }
+
+    /// Called whenever the world transformation of the owning shape changes
+    fn update_world_transform(&mut self, _world_transform: &Transformation) {}
+
+    /// Check whether `hit` was on one of the shapes contained in the geometry, see
+    /// `Shape::includes`
+    fn includes(&self, _hit: &Intersection) -> bool {
+        false
+    }
+
+    /// The shapes directly contained in the geometry
+    fn children(&self) -> &[Arc<Shape>] {
+        &[]
+    }
+}
+
+/// Object safe helpers implemented automatically for every geometry that is `Clone`
+pub trait GeometryBase {
+    fn box_clone(&self) -> Box<dyn Geometry>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Geometry + Clone + 'static> GeometryBase for T {
+    fn box_clone(&self) -> Box<dyn Geometry> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Geometry> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
 
 /// A general 3D shape
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Shape {
     transform: Transformation,
     inverse_transform: Transformation,
+    /// Combined transformation of all groups containing this shape
+    parent_transform: Transformation,
+    /// Inverse of parent_transform * transform, takes world space to object space
+    world_inverse_transform: Transformation,
+    /// Replaces `transform` for rays with a time if the shape moves
+    motion: Option<Motion>,
     pub material: Material,
-    pub intersect: IntersectFunc,
-    pub normal_at: NormalAtFunc,
-}
-
-impl fmt::Debug for Shape {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "Shape{{\nintersect: @ {:p},\nnormal_at: @ {:p},\ntransformation: {:?},\ninverse_transformation: {:?},\nmaterial: {:?}\n}}",
-            self.intersect as *const (), self.normal_at as *const (), self.transform, self.inverse_transform, self.material
-        )
-    }
+    geometry: Box<dyn Geometry>,
 }
 
 impl Shape {
-    pub fn new(funcs: ShapeFuncs, material: Material, transform: Transformation) -> Self {
-        let (intersect, normal_at) = funcs;
+    pub fn new(
+        geometry: impl Geometry + 'static,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
         let inverse_transform = transform
             .invert()
             .expect("Encountered non invertible matrix.");
-        Shape {
+        let mut shape = Shape {
+            world_inverse_transform: inverse_transform.clone(),
             transform,
             inverse_transform,
+            parent_transform: Transformation::identity(),
+            motion: None,
             material,
-            intersect,
-            normal_at,
+            geometry: Box::new(geometry),
+        };
+        shape.update_world_transform();
+        shape
+    }
+
+    pub fn geometry(&self) -> &dyn Geometry {
+        self.geometry.as_ref()
+    }
+
+    /// The geometry of the shape if it is of type T
+    pub fn geometry_as<T: Geometry + 'static>(&self) -> Option<&T> {
+        self.geometry.as_any().downcast_ref()
+    }
+
+    /// The geometry of the shape if it is of type T. Children added to the geometry have to be
+    /// given the world transformation of the shape.
+    pub(crate) fn geometry_as_mut<T: Geometry + 'static>(&mut self) -> Option<&mut T> {
+        self.geometry.as_any_mut().downcast_mut()
+    }
+
+    /// All intersections of a world space ray with the shape
+    pub fn intersect(self: &Arc<Self>, ray: &Ray) -> Option<Intersections> {
+        let mut xs = self.geometry.world_intersect(self, ray)?;
+        xs.set_time(ray.time);
+        Some(xs)
+    }
+
+    /// Calculates the world space normal at some world space point on the shape, `None` if the
+    /// shape has no surface of its own
+    pub fn normal_at(&self, point: &Point, hit: &Intersection) -> Option<Vec3D> {
+        let object_point = self.world_to_object_at(point, hit.time);
+        let object_normal = self.geometry.local_normal_at(&object_point, hit)?;
+        Some(self.normal_to_world_at(&object_normal, hit.time))
+    }
+
+    /// The surface coordinates of a hit on the shape used by textures with mesh mapping
+    pub fn texture_uv(&self, hit: &Intersection) -> (f64, f64) {
+        self.geometry.texture_uv(hit)
+    }
+
+    /// The world space axis aligned bounding box of the shape. Moving shapes are bounded over
+    /// their whole motion.
+    pub fn bounds(&self) -> BoundingBox {
+        match &self.motion {
+            None => self.geometry.world_bounds(self),
+            // points move on straight lines between keyframes, so the boxes at the keyframes
+            // contain the whole motion
+            Some(motion) => {
+                motion
+                    .keyframes()
+                    .iter()
+                    .fold(BoundingBox::empty(), |bounds, (_, transform)| {
+                        let world_transform = &self.parent_transform * transform;
+                        bounds.merge(&self.geometry.local_bounds().transform(&world_transform))
+                    })
+            }
+        }
+    }
+
+    /// The bounding box of the shape in the object space of its parent. Moving shapes are
+    /// bounded over their whole motion.
+    pub fn parent_space_bounds(&self) -> BoundingBox {
+        let local = self.geometry.local_bounds();
+        match &self.motion {
+            None => local.transform(&self.transform),
+            Some(motion) => motion
+                .keyframes()
+                .iter()
+                .fold(BoundingBox::empty(), |bounds, (_, transform)| {
+                    bounds.merge(&local.transform(transform))
+                }),
         }
     }
 
@@ -58,42 +209,117 @@ impl Shape {
         &self.inverse_transform
     }
 
+    /// Set the transformation of the shape, this stops moving shapes
     pub fn set_transform(&mut self, transformation: Transformation) {
+        self.motion = None;
         self.transform = transformation;
         self.inverse_transform = self.transform.invert().unwrap();
+        self.update_world_transform();
     }
 
+    /// Modify the transformation of the shape, this stops moving shapes
     pub fn modify_transform(&mut self, f: impl Fn(&mut Transformation) -> &mut Transformation) {
+        self.motion = None;
         f(&mut self.transform);
         self.inverse_transform = self.transform.invert().unwrap();
+        self.update_world_transform();
     }
-}
 
-/// Transform a ray to object space, and get all intersections via f_intersect
-pub fn base_shape_intersect(
-    shape: Arc<Shape>,
-    ray: &Ray,
-    f_intersect: impl Fn(Arc<Shape>, Ray) -> Option<Intersections>,
-) -> Option<Intersections> {
-    let inverse = shape.inverse_transform();
-    let ray2 = ray.transform(inverse);
-    f_intersect(shape, ray2)
-}
+    pub fn motion(&self) -> Option<&Motion> {
+        self.motion.as_ref()
+    }
 
-/// Applies default transformations, calls a function that operates on the object point and
-/// transforms the returned object vector into a world space vector
-pub fn base_shape_normal(
-    shape: Arc<Shape>,
-    point: &Point,
-    f_object_normal: impl Fn(Arc<Shape>, Point) -> Vec3D,
-) -> Vec3D {
-    let inverse = shape.inverse_transform();
-    let object_point = inverse * point;
-    let world_transform = inverse.transpose();
-    let object_normal = f_object_normal(shape, object_point);
-    let mut out = world_transform * object_normal;
-    out.set_w(0.0);
-    out.unit()
+    /// Let the shape move. The transformation at time 0 becomes the transformation of the
+    /// shape, which is used wherever time isn't taken into account. Groups and CSG shapes can't
+    /// move themselves, use an instance of them instead.
+    pub fn set_motion(&mut self, motion: Motion) -> Result<(), MotionError> {
+        if self.geometry_as::<Group>().is_some() || self.geometry_as::<Csg>().is_some() {
+            return Err(MotionError::CantMove);
+        }
+        self.transform = motion.at(0.);
+        self.inverse_transform = self.transform.invert().unwrap();
+        self.motion = Some(motion);
+        self.update_world_transform();
+        Ok(())
+    }
+
+    /// Combined transformation of all groups containing this shape
+    pub fn parent_transform(&self) -> &Transformation {
+        &self.parent_transform
+    }
+
+    /// Transformation from world space to object space taking all parents into account
+    pub fn world_inverse_transform(&self) -> &Transformation {
+        &self.world_inverse_transform
+    }
+
+    /// Transformation from world space to object space at some point in time
+    pub fn world_inverse_transform_at(&self, time: f64) -> Cow<'_, Transformation> {
+        match &self.motion {
+            None => Cow::Borrowed(&self.world_inverse_transform),
+            Some(motion) => Cow::Owned(
+                (&self.parent_transform * motion.at(time))
+                    .invert()
+                    .expect("Motions are checked to be invertible at all times."),
+            ),
+        }
+    }
+
+    /// Set the combined transformation of the parents of this shape. This is done
+    /// automatically when the shape is added to a group.
+    pub fn set_parent_transform(&mut self, transformation: Transformation) {
+        self.parent_transform = transformation;
+        self.update_world_transform();
+    }
+
+    /// Transformation from object space to world space taking all parents into account
+    pub fn world_transform(&self) -> Transformation {
+        &self.parent_transform * &self.transform
+    }
+
+    /// Recompute the world space transformation and pass it on to the children
+    fn update_world_transform(&mut self) {
+        let world_transform = self.world_transform();
+        self.world_inverse_transform = world_transform
+            .invert()
+            .expect("Encountered non invertible matrix.");
+        self.geometry.update_world_transform(&world_transform);
+    }
+
+    /// Check whether `hit` was on this shape or one of its descendants. A hit through instances
+    /// belongs to the outermost instance it went through rather than to the shared prototype,
+    /// so two instances of the same shape are told apart.
+    pub fn includes(&self, hit: &Intersection) -> bool {
+        let hit_shape = hit.instances.last().unwrap_or(&hit.object);
+        std::ptr::eq(self, hit_shape.as_ref()) || self.geometry.includes(hit)
+    }
+
+    /// The children of the shape, empty for shapes that don't contain other shapes
+    pub fn children(&self) -> &[Arc<Shape>] {
+        self.geometry.children()
+    }
+
+    /// Convert a point from world space to object space
+    pub fn world_to_object(&self, point: &Point) -> Point {
+        self.world_to_object_at(point, 0.)
+    }
+
+    /// Convert a point from world space to object space at some point in time
+    pub fn world_to_object_at(&self, point: &Point, time: f64) -> Point {
+        self.world_inverse_transform_at(time).as_ref() * point
+    }
+
+    /// Convert a normal from object space to world space
+    pub fn normal_to_world(&self, normal: &Vec3D) -> Vec3D {
+        self.normal_to_world_at(normal, 0.)
+    }
+
+    /// Convert a normal from object space to world space at some point in time
+    pub fn normal_to_world_at(&self, normal: &Vec3D, time: f64) -> Vec3D {
+        let mut out = self.world_inverse_transform_at(time).transpose() * normal;
+        out.set_w(0.0);
+        out.unit()
+    }
 }
 
 impl ApproxEq for &Shape {
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        approx_eq::EPSILON_F64,
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A rectangle in the xz plane centered at the origin and facing up. `width` is its extent along
+/// x and `depth` along z.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rectangle {
+    pub width: f64,
+    pub depth: f64,
+}
+
+impl Default for Rectangle {
+    /// The square from -1 to 1 along x and z
+    fn default() -> Self {
+        Rectangle {
+            width: 2.,
+            depth: 2.,
+        }
+    }
+}
+
+impl Rectangle {
+    /// Surface coordinates of a point on the rectangle, u runs along x and v along z from 0 to 1
+    pub fn uv_at(&self, point: &Point) -> (f64, f64) {
+        (
+            (point.x() / self.width + 0.5).clamp(0., 1.),
+            (point.z() / self.depth + 0.5).clamp(0., 1.),
+        )
+    }
+}
+
+impl Geometry for Rectangle {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        if ray.direction.y().abs() < EPSILON_F64 {
+            return None;
+        }
+        let t = -ray.origin.y() / ray.direction.y();
+        let p = ray.position(t);
+        if p.x().abs() > self.width / 2. || p.z().abs() > self.depth / 2. {
+            return None;
+        }
+        let (u, v) = self.uv_at(&p);
+        Some(Intersections::new(vec![Intersection::new_with_uv(
+            t,
+            Arc::clone(shape),
+            u,
+            v,
+        )]))
+    }
+
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(vector(0., 1., 0.))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let (x, z) = (self.width / 2., self.depth / 2.);
+        BoundingBox::new(point(-x, 0., -z), point(x, 0., z))
+    }
+}
+
+impl Shape {
+    /// A `width` by `depth` rectangle in the xz plane
+    pub fn new_rectangle(
+        width: f64,
+        depth: f64,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(Rectangle { width, depth }, material, transform)
+    }
+
+    pub fn default_rectangle() -> Self {
+        Self::new(
+            Rectangle::default(),
+            Material::default(),
+            Transformation::identity(),
+        )
+    }
+}
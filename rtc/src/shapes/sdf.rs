@@ -0,0 +1,221 @@
+use std::{fmt, sync::Arc};
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// Offset used for the central differences approximating the gradient of a distance function
+const NORMAL_DELTA: f64 = 1e-5;
+
+/// A signed distance function: negative inside of the described surface, positive outside of it.
+/// The absolute value must never overestimate the distance to the surface, otherwise sphere
+/// tracing may step through it.
+#[derive(Clone)]
+pub struct DistanceFunction(Arc<dyn Fn(&Point) -> f64 + Send + Sync>);
+
+impl fmt::Debug for DistanceFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DistanceFunction @ {:p}",
+            Arc::as_ptr(&self.0) as *const ()
+        )
+    }
+}
+
+impl DistanceFunction {
+    pub fn new(f: impl Fn(&Point) -> f64 + Send + Sync + 'static) -> Self {
+        DistanceFunction(Arc::new(f))
+    }
+
+    pub fn distance(&self, p: &Point) -> f64 {
+        (self.0)(p)
+    }
+
+    /// Sphere around the origin
+    pub fn sphere(radius: f64) -> Self {
+        Self::new(move |p| (p.x().powi(2) + p.y().powi(2) + p.z().powi(2)).sqrt() - radius)
+    }
+
+    /// Axis aligned box around the origin, `half_extents` is the distance of the faces to the
+    /// origin along each axis
+    pub fn cuboid(half_extents: Vec3D) -> Self {
+        Self::new(move |p| {
+            let q = [
+                p.x().abs() - half_extents.x(),
+                p.y().abs() - half_extents.y(),
+                p.z().abs() - half_extents.z(),
+            ];
+            let outside = q.iter().map(|c| c.max(0.).powi(2)).sum::<f64>().sqrt();
+            let inside = q[0].max(q[1].max(q[2])).min(0.);
+            outside + inside
+        })
+    }
+
+    /// Torus in the xz plane around the y axis
+    pub fn torus(major_radius: f64, minor_radius: f64) -> Self {
+        Self::new(move |p| {
+            let ring = (p.x().powi(2) + p.z().powi(2)).sqrt() - major_radius;
+            (ring.powi(2) + p.y().powi(2)).sqrt() - minor_radius
+        })
+    }
+
+    /// Everything that's in either shape
+    pub fn union(self, other: Self) -> Self {
+        Self::new(move |p| self.distance(p).min(other.distance(p)))
+    }
+
+    /// Everything that's in both shapes
+    pub fn intersection(self, other: Self) -> Self {
+        Self::new(move |p| self.distance(p).max(other.distance(p)))
+    }
+
+    /// Everything that's in this but not in the other shape
+    pub fn subtraction(self, other: Self) -> Self {
+        Self::new(move |p| self.distance(p).max(-other.distance(p)))
+    }
+
+    /// Union that blends both shapes where they're closer than about `k` to each other
+    pub fn smooth_union(self, other: Self, k: f64) -> Self {
+        Self::new(move |p| {
+            let d1 = self.distance(p);
+            let d2 = other.distance(p);
+            let h = (0.5 + 0.5 * (d2 - d1) / k).clamp(0., 1.);
+            d2 * (1. - h) + d1 * h - k * h * (1. - h)
+        })
+    }
+
+    /// Infinitely repeat the shape with the given period along each axis. The shape should fit
+    /// into a single cell around the origin. A period of zero disables repetition on that axis.
+    pub fn repetition(self, period: Vec3D) -> Self {
+        let repeat = |c: f64, period: f64| {
+            if period == 0. {
+                c
+            } else {
+                (c + 0.5 * period).rem_euclid(period) - 0.5 * period
+            }
+        };
+        Self::new(move |p| {
+            self.distance(&point(
+                repeat(p.x(), period.x()),
+                repeat(p.y(), period.y()),
+                repeat(p.z(), period.z()),
+            ))
+        })
+    }
+
+    /// Move the shape by `offset`
+    pub fn translated(self, offset: Vec3D) -> Self {
+        Self::new(move |p| self.distance(&(p - &offset)))
+    }
+}
+
+/// An implicit surface given by a signed distance function that is intersected by sphere tracing
+#[derive(Clone, Debug)]
+pub struct Sdf {
+    pub distance: DistanceFunction,
+    /// Object space bounds of the surface, rays are only traced inside of them
+    pub bounds: BoundingBox,
+    /// Maximum number of steps taken along a ray
+    pub max_steps: usize,
+    /// Points closer than this to the surface count as being on it
+    pub epsilon: f64,
+    /// How far rays are traced if the bounds are infinite
+    pub max_distance: f64,
+}
+
+impl Sdf {
+    pub fn new(distance: DistanceFunction, bounds: BoundingBox) -> Self {
+        Sdf {
+            distance,
+            bounds,
+            max_steps: 256,
+            epsilon: 1e-6,
+            max_distance: 1e3,
+        }
+    }
+
+    /// Distances along a ray at which it crosses the surface. `t` is measured in units of the
+    /// (not necessarily normalized) ray direction.
+    pub fn march(&self, ray: &Ray) -> Vec<f64> {
+        let speed = ray.direction.clone().mag();
+        let (t_start, t_end) = if self.bounds.is_finite() {
+            match self.bounds.clip(ray, -f64::INFINITY, f64::INFINITY) {
+                Some(range) => range,
+                None => return vec![],
+            }
+        } else {
+            (0., self.max_distance / speed)
+        };
+        let mut ts = vec![];
+        let mut t = t_start;
+        let mut on_surface = false;
+        for _ in 0..self.max_steps {
+            if t > t_end {
+                break;
+            }
+            let d = self.distance.distance(&ray.position(t)).abs();
+            if d < self.epsilon {
+                // only count the first step into the thin shell around the surface
+                if !on_surface {
+                    ts.push(t);
+                }
+                on_surface = true;
+            } else {
+                on_surface = false;
+            }
+            // always make some progress, the distance stays close to 0 right at the surface
+            t += d.max(self.epsilon) / speed;
+        }
+        ts
+    }
+}
+
+impl Geometry for Sdf {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let ts = self.march(ray);
+        if ts.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                ts.into_iter()
+                    .map(|t| Intersection::new(t, Arc::clone(shape)))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, p: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        let gradient = |offset: Vec3D| {
+            self.distance.distance(&(p + &offset)) - self.distance.distance(&(p - &offset))
+        };
+        Some(vector(
+            gradient(vector(NORMAL_DELTA, 0., 0.)),
+            gradient(vector(0., NORMAL_DELTA, 0.)),
+            gradient(vector(0., 0., NORMAL_DELTA)),
+        ))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.bounds.clone()
+    }
+}
+
+impl Shape {
+    /// An implicit surface with default tracing parameters, use `Shape::new` with an `Sdf` to
+    /// change them
+    pub fn new_sdf(
+        distance: DistanceFunction,
+        bounds: BoundingBox,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(Sdf::new(distance, bounds), material, transform)
+    }
+}
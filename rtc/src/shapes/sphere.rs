@@ -5,20 +5,20 @@ use super::prelude::*;
 use crate::{
     primitives::{
         ray::Ray,
-        vector::{Point, ScalarProd, Transformation, Vec3D},
+        vector::{point, Point, ScalarProd, Transformation, Vec3D},
     },
     shading::Material,
 };
 
-pub static SPHERE: ShapeFuncs = (intersect, normal_at);
+/// A sphere of radius 1 around the origin
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Sphere;
 
-fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
-    base_shape_intersect(shape, ray, |shape, _| {
-        let inverse = shape.inverse_transform();
-        let ray2 = ray.transform(inverse);
-        let sphere_to_ray = (&ray2.origin) - Point::origin();
-        let a = (&ray2.direction).scalar_prod(&ray2.direction);
-        let b = 2.0 * (&ray2.direction).scalar_prod(&sphere_to_ray);
+impl Geometry for Sphere {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        let sphere_to_ray = (&ray.origin) - Point::origin();
+        let a = (&ray.direction).scalar_prod(&ray.direction);
+        let b = 2.0 * (&ray.direction).scalar_prod(&sphere_to_ray);
         let c = (&sphere_to_ray).scalar_prod(&sphere_to_ray) - 1.0;
         let discriminant = b.powi(2) - 4.0 * a * c;
         if discriminant < 0.0 {
@@ -35,23 +35,25 @@ fn intersect(shape: Arc<Shape>, ray: &Ray) -> Option<Intersections> {
                     .collect::<Vec<_>>(),
             ))
         }
-    })
-}
+    }
 
-fn normal_at(shape: Arc<Shape>, point: &Point) -> Vec3D {
-    base_shape_normal(shape, point, |_, object_point| {
-        object_point - Point::origin()
-    })
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(point - Point::origin())
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1., -1., -1.), point(1., 1., 1.))
+    }
 }
 
 impl Shape {
     pub fn new_sphere(material: Material, transform: Transformation) -> Self {
-        Self::new(SPHERE, material, transform)
+        Self::new(Sphere, material, transform)
     }
 }
 
 impl Default for Shape {
     fn default() -> Self {
-        Self::new(SPHERE, Material::default(), Transformation::identity())
+        Self::new(Sphere, Material::default(), Transformation::identity())
     }
 }
@@ -0,0 +1,93 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        polynomial::solve_quartic,
+        ray::Ray,
+        vector::{point, vector, Point, ScalarProd, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A torus lying in the xz plane around the y axis. The center of the tube is `major_radius`
+/// away from the origin, the tube itself has a radius of `minor_radius`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Torus {
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Torus {
+    pub fn new(major_radius: f64, minor_radius: f64) -> Self {
+        Torus {
+            major_radius,
+            minor_radius,
+        }
+    }
+}
+
+impl Geometry for Torus {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        // Solve for the distance along the normalized direction, scaled shapes would otherwise
+        // shrink the leading coefficient (d.d)^2 below the precision of the solver
+        let length = ray.direction.mag();
+        let d = &(&ray.direction / length);
+        // Move the origin of the ray to the point closest to the center of the torus, this
+        // keeps the coefficients of the quartic small for rays starting far away.
+        let s_shift = -(&(&ray.origin - Point::origin())).scalar_prod(d);
+        let o = &ray.origin + &(d * s_shift);
+
+        let rr = self.major_radius.powi(2);
+        let od = 2. * (o.x() * d.x() + o.y() * d.y() + o.z() * d.z());
+        let l = o.x().powi(2) + o.y().powi(2) + o.z().powi(2) + rr - self.minor_radius.powi(2);
+        let ts = solve_quartic(
+            1.,
+            2. * od,
+            od * od + 2. * l - 4. * rr * (d.x().powi(2) + d.z().powi(2)),
+            2. * od * l - 8. * rr * (o.x() * d.x() + o.z() * d.z()),
+            l * l - 4. * rr * (o.x().powi(2) + o.z().powi(2)),
+        );
+        if ts.is_empty() {
+            None
+        } else {
+            Some(Intersections::new(
+                ts.into_iter()
+                    .map(|t| Intersection::new((t + s_shift) / length, Arc::clone(shape)))
+                    .collect(),
+            ))
+        }
+    }
+
+    fn local_normal_at(&self, point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        // gradient of (|p|^2 + R^2 - r^2)^2 - 4R^2(x^2 + z^2)
+        let sum = point.x().powi(2) + point.y().powi(2) + point.z().powi(2);
+        let rr = self.major_radius.powi(2);
+        let r2 = self.minor_radius.powi(2);
+        Some(vector(
+            point.x() * (sum - rr - r2),
+            point.y() * (sum + rr - r2),
+            point.z() * (sum - rr - r2),
+        ))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        let outer = self.major_radius + self.minor_radius;
+        BoundingBox::new(
+            point(-outer, -self.minor_radius, -outer),
+            point(outer, self.minor_radius, outer),
+        )
+    }
+}
+
+impl Shape {
+    pub fn new_torus(
+        major_radius: f64,
+        minor_radius: f64,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(Torus::new(major_radius, minor_radius), material, transform)
+    }
+}
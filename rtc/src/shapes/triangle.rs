@@ -0,0 +1,191 @@
+use std::sync::Arc;
+
+use super::prelude::*;
+
+use crate::{
+    primitives::{
+        ray::Ray,
+        vector::{CrossProd, Point, ScalarProd, Transformation, Vec3D},
+    },
+    shading::Material,
+};
+
+/// A flat triangle spanned by three points in object space
+#[derive(Clone, Debug)]
+pub struct Triangle {
+    pub p1: Point,
+    pub p2: Point,
+    pub p3: Point,
+    /// Edge from p1 to p2
+    pub e1: Vec3D,
+    /// Edge from p1 to p3
+    pub e2: Vec3D,
+    pub normal: Vec3D,
+    /// Texture coordinates at p1, p2 and p3. Without them the barycentric coordinates of a hit
+    /// are used.
+    pub texture_coords: Option<[(f64, f64); 3]>,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = &p2 - &p1;
+        let e2 = &p3 - &p1;
+        let normal = (&e2).cross(&e1).unit();
+        Triangle {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+            texture_coords: None,
+        }
+    }
+
+    /// Interpolate the texture coordinates using the barycentric coordinates u and v
+    pub fn texture_uv_at(&self, u: f64, v: f64) -> (f64, f64) {
+        match self.texture_coords {
+            Some([(u1, v1), (u2, v2), (u3, v3)]) => (
+                u2 * u + u3 * v + u1 * (1. - u - v),
+                v2 * u + v3 * v + v1 * (1. - u - v),
+            ),
+            None => (u, v),
+        }
+    }
+
+    /// Intersection of an object space ray with the triangle.
+    /// Returns t as well as the barycentric coordinates u and v of the intersection.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f64, f64, f64)> {
+        moller_trumbore(ray, &self.p1, &self.e1, &self.e2)
+    }
+}
+
+/// Relative size of the determinant below which rays count as parallel to a triangle
+const PARALLEL_EPSILON: f64 = 1e-12;
+
+/// Möller-Trumbore intersection of a ray with the triangle at `p1` spanned by the edges `e1` and
+/// `e2`. The determinant is compared relative to the lengths of the edges and the direction, so
+/// tiny triangles of tessellated surfaces are hit just like large ones. Returns t and the
+/// barycentric coordinates u and v.
+fn moller_trumbore(ray: &Ray, p1: &Point, e1: &Vec3D, e2: &Vec3D) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = (&ray.direction).cross(e2);
+    let det = e1.scalar_prod(&dir_cross_e2);
+    let scale = e1.clone().mag() * e2.clone().mag() * ray.direction.clone().mag();
+    if det.abs() <= PARALLEL_EPSILON * scale {
+        return None;
+    }
+    let f = 1.0 / det;
+    let p1_to_origin = &ray.origin - p1;
+    let u = f * (&p1_to_origin).scalar_prod(&dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let origin_cross_e1 = (&p1_to_origin).cross(e1);
+    let v = f * (&ray.direction).scalar_prod(&origin_cross_e1);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some((f * e2.scalar_prod(&origin_cross_e1), u, v))
+}
+
+/// Intersection of a ray with the triangle spanned by three points, for shapes that don't keep
+/// `Triangle`s around. Returns t and the barycentric coordinates u and v.
+pub(super) fn intersect_triangle(
+    ray: &Ray,
+    p1: &Point,
+    p2: &Point,
+    p3: &Point,
+) -> Option<(f64, f64, f64)> {
+    moller_trumbore(ray, p1, &(p2 - p1), &(p3 - p1))
+}
+
+/// A triangle with normals given at each vertex that are interpolated across its surface
+#[derive(Clone, Debug)]
+pub struct SmoothTriangle {
+    pub triangle: Triangle,
+    pub n1: Vec3D,
+    pub n2: Vec3D,
+    pub n3: Vec3D,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vec3D, n2: Vec3D, n3: Vec3D) -> Self {
+        SmoothTriangle {
+            triangle: Triangle::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    /// Interpolate the vertex normals using the barycentric coordinates u and v
+    pub fn normal_at(&self, u: f64, v: f64) -> Vec3D {
+        &self.n2 * u + &self.n3 * v + &self.n1 * (1. - u - v)
+    }
+}
+
+impl Geometry for Triangle {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        self.intersect(ray).map(|(t, u, v)| {
+            Intersections::new(vec![Intersection::new_with_uv(t, Arc::clone(shape), u, v)])
+        })
+    }
+
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(self.normal.clone())
+    }
+
+    fn texture_uv(&self, hit: &Intersection) -> (f64, f64) {
+        self.texture_uv_at(hit.u, hit.v)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(vec![&self.p1, &self.p2, &self.p3])
+    }
+}
+
+impl Geometry for SmoothTriangle {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        self.triangle.local_intersect(shape, ray)
+    }
+
+    fn local_normal_at(&self, _point: &Point, hit: &Intersection) -> Option<Vec3D> {
+        Some(self.normal_at(hit.u, hit.v))
+    }
+
+    fn texture_uv(&self, hit: &Intersection) -> (f64, f64) {
+        self.triangle.texture_uv_at(hit.u, hit.v)
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        self.triangle.local_bounds()
+    }
+}
+
+impl Shape {
+    pub fn new_triangle(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        Self::new(Triangle::new(p1, p2, p3), material, transform)
+    }
+
+    /// A triangle through `points` with the vertex `normals` in the same order
+    pub fn new_smooth_triangle(
+        points: [Point; 3],
+        normals: [Vec3D; 3],
+        material: Material,
+        transform: Transformation,
+    ) -> Self {
+        let [p1, p2, p3] = points;
+        let [n1, n2, n3] = normals;
+        Self::new(
+            SmoothTriangle::new(p1, p2, p3, n1, n2, n3),
+            material,
+            transform,
+        )
+    }
+}
@@ -1,6 +1,8 @@
 #[allow(dead_code)]
 pub mod typelevel_nums;
 
+pub mod random;
+
 /// Clamp function, see https://github.com/rust-lang/rust/issues/44095
 /// Unstable as of writing this (23.08.19)
 pub fn clamp<N: PartialOrd>(a: N, min: N, max: N) -> N {
@@ -0,0 +1,65 @@
+//! A small, seedable pseudo random number generator. Renders have to be reproducible and each
+//! thread or pixel gets its own generator, so a fast generator with a tiny state is all we need.
+
+/// SplitMix64 generator, see https://prng.di.unimi.it/splitmix64.c
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+
+    /// A generator whose seed is derived from several values, e.g. the coordinates of a pixel
+    pub fn from_seeds(seeds: &[u64]) -> Self {
+        let mut rng = Rng::new(0);
+        for seed in seeds {
+            rng.state ^= seed;
+            rng.next_u64();
+        }
+        rng
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        // the upper 53 bits fill the mantissa
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(
+            Rng::from_seeds(&[1, 2]).next_u64(),
+            Rng::from_seeds(&[2, 1]).next_u64()
+        );
+    }
+
+    #[test]
+    fn unit_interval() {
+        let mut rng = Rng::new(7);
+        let samples: Vec<_> = (0..1000).map(|_| rng.next_f64()).collect();
+        assert!(samples.iter().all(|x| (0. ..1.).contains(x)));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.5).abs() < 0.05);
+    }
+}
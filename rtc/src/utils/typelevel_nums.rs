@@ -7,7 +7,7 @@
 
 use std::{fmt, marker::PhantomData};
 
-/// The mathematical set Z
+// The mathematical set Z
 //pub trait Int {}
 
 /// The mathematical set N(+0)
@@ -0,0 +1,68 @@
+//! Crates using the ray tracer can add their own primitives by implementing `Geometry`
+
+use std::sync::Arc;
+
+use rtc::{
+    assert_approx_eq,
+    primitives::{
+        ray::Ray,
+        vector::{point, vector, Point, Transformation, Vec3D},
+    },
+    scenes::World,
+    shading::{Color, Material, PointLight},
+    shapes::{BoundingBox, Geometry, Intersection, Intersections, Shape},
+};
+
+/// The square from -1 to 1 along x and y in the plane z = 0, facing towards negative z
+#[derive(Clone, Debug)]
+struct Square;
+
+impl Geometry for Square {
+    fn local_intersect(&self, shape: &Arc<Shape>, ray: &Ray) -> Option<Intersections> {
+        if ray.direction.z() == 0. {
+            return None;
+        }
+        let t = -ray.origin.z() / ray.direction.z();
+        let p = ray.position(t);
+        if p.x().abs() > 1. || p.y().abs() > 1. {
+            return None;
+        }
+        Some(Intersections::new(vec![Intersection::new(
+            t,
+            Arc::clone(shape),
+        )]))
+    }
+
+    fn local_normal_at(&self, _point: &Point, _hit: &Intersection) -> Option<Vec3D> {
+        Some(vector(0., 0., -1.))
+    }
+
+    fn local_bounds(&self) -> BoundingBox {
+        BoundingBox::new(point(-1., -1., 0.), point(1., 1., 0.))
+    }
+}
+
+#[test]
+fn custom_geometry_in_world() {
+    let square = Shape::new(
+        Square,
+        Material::default(),
+        Transformation::new_scaling(2., 2., 2.),
+    );
+    assert!(square.geometry_as::<Square>().is_some());
+    let light = PointLight::new(point(0., 0., -10.), Color::white());
+    let world = World::new(vec![square], vec![Box::new(light)]);
+
+    let r = Ray::new(point(0., 0., -5.), vector(0., 0., 1.));
+    // ambient, diffuse and specular light of the default material facing the light
+    assert_approx_eq!(world.color_at(&r, 5), Color::white() * 1.9);
+
+    let r = Ray::new(point(1.5, 1.5, -5.), vector(0., 0., 1.));
+    let xs = world.intersect(&r);
+    assert_eq!(xs.len(), 1);
+    assert_approx_eq!(xs[0].t, 5.);
+
+    // the scaling of the shape makes the square 4 units wide
+    let r = Ray::new(point(2.5, 0., -5.), vector(0., 0., 1.));
+    assert_eq!(world.intersect(&r).len(), 0);
+}